@@ -1,3 +1,55 @@
+use deno_core::snapshot::{create_snapshot, CreateSnapshotOptions};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+#[path = "src/scripts/snapshot.rs"]
+mod snapshot;
+#[path = "src/scripts/loader/transpile.rs"]
+mod transpile;
+
 fn main() {
+    export_deno_ast_version();
+    create_startup_snapshot();
     tauri_build::build()
 }
+
+/// Transpiles and evaluates the `snip` extension, so script runtimes can start from the result
+/// instead of doing so for every run
+fn create_startup_snapshot() {
+    let output = create_snapshot(
+        CreateSnapshotOptions {
+            cargo_manifest_dir: env!("CARGO_MANIFEST_DIR"),
+            startup_snapshot: None,
+            skip_op_registration: false,
+            extensions: vec![snapshot::snip::init_ops_and_esm()],
+            extension_transpiler: Some(Rc::new(transpile::transpile_extension)),
+            with_runtime_cb: None,
+        },
+        None,
+    )
+    .expect("Could not create the startup snapshot");
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+    std::fs::write(out_dir.join("SNIP_SNAPSHOT.bin"), output.output)
+        .expect("Could not write the startup snapshot");
+    for path in output.files_loaded_during_snapshot {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+/// Makes the locked version of deno_ast available as `SNIP_DENO_AST_VERSION`, so the transpile
+/// cache can tell which version transpiled its entries
+fn export_deno_ast_version() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let lock_file = std::fs::read_to_string("Cargo.lock").expect("Could not read Cargo.lock");
+    let version = lock_file
+        .split("[[package]]")
+        .filter(|package| package.lines().any(|line| line == "name = \"deno_ast\""))
+        .find_map(|package| {
+            package
+                .lines()
+                .find_map(|line| line.strip_prefix("version = \"")?.strip_suffix('"'))
+        })
+        .expect("Cargo.lock does not contain deno_ast");
+    println!("cargo:rustc-env=SNIP_DENO_AST_VERSION={}", version);
+}
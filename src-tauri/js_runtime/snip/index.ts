@@ -62,6 +62,50 @@
         text: string
     }
 
+    interface DocumentRange {
+        start?: number
+        end?: number
+    }
+
+    interface ReadChunksOptions extends DocumentRange {
+        chunkSize?: number
+    }
+
+    const DEFAULT_CHUNK_SIZE = 64 * 1024
+
+    type StatusLevel = "info" | "success" | "warning" | "error"
+
+    interface Diagnostic {
+        start: number
+        end: number
+        severity: "info" | "warning" | "error"
+        message: string
+    }
+
+    type Decoration =
+        | { kind: "highlight", start: number, end: number, title?: string }
+        | { kind: "gutterMarker", position: number, text: string, title?: string }
+
+    interface DocumentAnnotations {
+        diagnostics?: Diagnostic[]
+        decorations?: Decoration[]
+    }
+
+    // The document as it was when the command started
+    interface DocumentInfo {
+        language: string | null
+        length: number
+        lineCount: number
+        filePath: string | null
+        lineEnding: "lf" | "crlf" | "cr"
+    }
+
+    // A cursor when head is left out
+    interface SelectionRange {
+        anchor: number
+        head?: number
+    }
+
     class Editor {
         private editorHandle: number|undefined
 
@@ -97,6 +141,14 @@
             await Deno.core.ops.snip_op_set_error(editorHandle, error)
         }
 
+        // Shows a message in the status bar. It stays until the next command runs, unless a
+        // duration in milliseconds is given.
+        public async setStatus(level: StatusLevel, message: string, duration?: number) {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_set_status(editorHandle, level, message, duration ?? null)
+        }
+
         public async getPartialText(start: number, end: number): Promise<string> {
             const editorHandle = this.getEditorHandle()
             // @ts-ignore
@@ -118,10 +170,150 @@
             // @ts-ignore
             await Deno.core.ops.snip_op_replace_selections(editorHandle, replacements)
         }
+
+        // The first selection becomes the main selection, unless another index is given
+        public async setSelections(selections: SelectionRange[], mainSelectionIndex: number = 0): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_set_selections(editorHandle, {
+                mainSelectionIndex,
+                selections: selections.map(({ anchor, head }) => ({ anchor, head: head ?? anchor })),
+            })
+        }
+
+        public async insertAt(position: number, text: string): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_insert_at(editorHandle, position, text)
+        }
+
+        public async replaceRange(start: number, end: number, text: string): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_replace_range(editorHandle, start, end, text)
+        }
+
+        // The position of the head of the main selection
+        public async getCursor(): Promise<number> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_get_cursor(editorHandle)
+        }
+
+        public async getDocumentLength(): Promise<number> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_get_document_length(editorHandle)
+        }
+
+        public async getDocumentInfo(): Promise<DocumentInfo> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_get_document_info(editorHandle)
+        }
+
+        // Switches the highlighting of the document, e.g. to "yaml"
+        public async setLanguage(language: string): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_set_language(editorHandle, language)
+        }
+
+        // Replaces the diagnostics and decorations of the document, which stay until it is edited
+        public async setAnnotations(annotations: DocumentAnnotations): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_set_annotations(editorHandle, annotations)
+        }
+
+        // Reads the document piece by piece, so large documents never have to be held in full
+        public async *readChunks(options: ReadChunksOptions = {}): AsyncGenerator<string> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            const readerId: number = await Deno.core.ops.snip_op_open_document_reader(
+                editorHandle,
+                options.start ?? 0,
+                options.end ?? null,
+                options.chunkSize ?? DEFAULT_CHUNK_SIZE,
+            )
+            try {
+                while (true) {
+                    // @ts-ignore
+                    const chunk: string | null = await Deno.core.ops.snip_op_read_document_chunk(readerId)
+                    if (chunk === null) {
+                        return
+                    }
+                    yield chunk
+                }
+            } finally {
+                // @ts-ignore
+                Deno.core.tryClose(readerId)
+            }
+        }
+
+        // Lines are yielded without their line break
+        public async *readLines(range: DocumentRange = {}): AsyncGenerator<string> {
+            let rest = ""
+            for await (const chunk of this.readChunks(range)) {
+                const lines = (rest + chunk).split("\n")
+                rest = lines.pop()!
+                for (const line of lines) {
+                    yield line.endsWith("\r") ? line.slice(0, -1) : line
+                }
+            }
+            if (rest !== "") {
+                yield rest
+            }
+        }
+
+        public async appendText(text: string): Promise<void> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            await Deno.core.ops.snip_op_append_text(editorHandle, text)
+        }
+
+        public async getParameters(): Promise<Record<string, string | number | boolean>> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_get_parameters(editorHandle)
+        }
+
+        public async prompt(message: string, defaultValue?: string): Promise<string> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_prompt(editorHandle, message, defaultValue ?? null)
+        }
+
+        public async confirm(message: string): Promise<boolean> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_confirm(editorHandle, message)
+        }
+
+        public async pickFromList(message: string, options: string[]): Promise<string> {
+            const editorHandle = this.getEditorHandle()
+            // @ts-ignore
+            const index = await Deno.core.ops.snip_op_pick_from_list(editorHandle, message, options)
+            return options[index]
+        }
     }
     // @ts-ignore
     globalThis.Editor = new Editor()
 
+    class Files {
+        public async readText(path: string): Promise<string> {
+            // @ts-ignore
+            return await Deno.core.ops.snip_op_read_text_file(path)
+        }
+
+        public async writeText(path: string, text: string): Promise<void> {
+            // @ts-ignore
+            await Deno.core.ops.snip_op_write_text_file(path, text)
+        }
+    }
+    // @ts-ignore
+    globalThis.Files = new Files()
+
 
     /**
      * @param {string} data
@@ -143,4 +335,480 @@
 
     globalThis.atob = atob
     globalThis.btoa = btoa
+
+    type DigestAlgorithm = "SHA-1" | "SHA-256" | "SHA-384" | "SHA-512"
+    type BinaryEncoding = "hex" | "base32" | "base64" | "base64url"
+
+    // Strings are hashed as UTF-8
+    function toBytes(data: string | ArrayBuffer | ArrayBufferView): Uint8Array {
+        if (typeof data === "string") {
+            // @ts-ignore
+            return Deno.core.encode(data)
+        }
+        if (data instanceof ArrayBuffer) {
+            return new Uint8Array(data)
+        }
+        return new Uint8Array(data.buffer, data.byteOffset, data.byteLength)
+    }
+
+    function algorithmName(algorithm: string | { name: string }): string {
+        return typeof algorithm === "string" ? algorithm : algorithm.name
+    }
+
+    class Cryptography {
+        public async digest(algorithm: DigestAlgorithm, data: string | ArrayBuffer | ArrayBufferView): Promise<Uint8Array> {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_crypto_digest(algorithm, toBytes(data))
+        }
+
+        public async hmac(algorithm: DigestAlgorithm, key: string | ArrayBuffer | ArrayBufferView, data: string | ArrayBuffer | ArrayBufferView): Promise<Uint8Array> {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_crypto_hmac(algorithm, toBytes(key), toBytes(data))
+        }
+
+        public randomBytes(length: number): Uint8Array {
+            const bytes = new Uint8Array(length)
+            // @ts-ignore
+            Deno.core.ops.op_snip_crypto_random_bytes(bytes)
+            return bytes
+        }
+    }
+    // @ts-ignore
+    globalThis.Cryptography = new Cryptography()
+
+    class Encoding {
+        public encode(encoding: BinaryEncoding, data: string | ArrayBuffer | ArrayBufferView): string {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_encode(encoding, toBytes(data))
+        }
+
+        public decode(encoding: BinaryEncoding, text: string): Uint8Array {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_decode(encoding, text)
+        }
+
+        public decodeToText(encoding: BinaryEncoding, text: string): string {
+            // @ts-ignore
+            return Deno.core.decode(this.decode(encoding, text))
+        }
+    }
+    // @ts-ignore
+    globalThis.Encoding = new Encoding()
+
+    // The part of the Web Crypto API that scripts copied from elsewhere commonly use
+    const crypto = {
+        getRandomValues<T extends ArrayBufferView>(array: T): T {
+            // @ts-ignore
+            Deno.core.ops.op_snip_crypto_random_bytes(toBytes(array))
+            return array
+        },
+        randomUUID(): string {
+            const bytes = new Cryptography().randomBytes(16)
+            bytes[6] = (bytes[6] & 0x0f) | 0x40
+            bytes[8] = (bytes[8] & 0x3f) | 0x80
+            // @ts-ignore
+            const hex: string = Deno.core.ops.op_snip_encode("hex", bytes)
+            return `${hex.slice(0, 8)}-${hex.slice(8, 12)}-${hex.slice(12, 16)}-${hex.slice(16, 20)}-${hex.slice(20)}`
+        },
+        subtle: {
+            async digest(algorithm: string | { name: string }, data: ArrayBuffer | ArrayBufferView): Promise<ArrayBuffer> {
+                // @ts-ignore
+                const digest: Uint8Array = Deno.core.ops.op_snip_crypto_digest(algorithmName(algorithm), toBytes(data))
+                return digest.buffer.slice(digest.byteOffset, digest.byteOffset + digest.byteLength)
+            },
+        },
+    }
+    // @ts-ignore
+    globalThis.crypto = crypto
+
+    type TextDecoderEncoding = "utf-8" | "utf-16le" | "utf-16be"
+
+    const textDecoderLabels: Record<string, TextDecoderEncoding> = {
+        "utf-8": "utf-8",
+        "utf8": "utf-8",
+        "unicode-1-1-utf-8": "utf-8",
+        "utf-16": "utf-16le",
+        "utf-16le": "utf-16le",
+        "utf-16be": "utf-16be",
+    }
+
+    class TextEncoder {
+        public get encoding(): string {
+            return "utf-8"
+        }
+
+        public encode(input: string = ""): Uint8Array {
+            // @ts-ignore
+            return Deno.core.encode(String(input))
+        }
+
+        public encodeInto(source: string, destination: Uint8Array): { read: number, written: number } {
+            // @ts-ignore
+            const [read, written] = Deno.core.ops.op_snip_text_encode_into(String(source), destination)
+            return { read, written }
+        }
+    }
+
+    class TextDecoder {
+        private readonly label: TextDecoderEncoding
+        public readonly fatal: boolean
+        public readonly ignoreBOM: boolean
+
+        constructor(label: string = "utf-8", options: { fatal?: boolean, ignoreBOM?: boolean } = {}) {
+            const encoding = textDecoderLabels[String(label).trim().toLowerCase()]
+            if (encoding === undefined) {
+                throw new RangeError(`The encoding label provided ('${label}') is not supported`)
+            }
+            this.label = encoding
+            this.fatal = options.fatal === true
+            this.ignoreBOM = options.ignoreBOM === true
+        }
+
+        public get encoding(): string {
+            return this.label
+        }
+
+        public decode(input: ArrayBuffer | ArrayBufferView = new Uint8Array()): string {
+            try {
+                // @ts-ignore
+                return Deno.core.ops.op_snip_text_decode(this.label, toBytes(input), this.fatal, this.ignoreBOM)
+            } catch (e) {
+                throw new TypeError((e as Error).message)
+            }
+        }
+    }
+    // @ts-ignore
+    globalThis.TextEncoder = TextEncoder
+    // @ts-ignore
+    globalThis.TextDecoder = TextDecoder
+
+    interface UrlComponents {
+        href: string
+        origin: string
+        protocol: string
+        username: string
+        password: string
+        host: string
+        hostname: string
+        port: string
+        pathname: string
+        search: string
+        hash: string
+    }
+
+    type SearchParamsInit = string | [string, string][] | Record<string, string> | URLSearchParams
+
+    class URLSearchParams {
+        private list: [string, string][] = []
+        // Set when the parameters belong to a URL, which is kept in sync
+        private url: URL | null = null
+
+        constructor(init: SearchParamsInit = "") {
+            if (typeof init === "string") {
+                // @ts-ignore
+                this.list = Deno.core.ops.op_snip_url_parse_search_params(init)
+            } else if (init instanceof URLSearchParams) {
+                this.list = init.list.map(([name, value]) => [name, value])
+            } else if (Array.isArray(init) || Symbol.iterator in init) {
+                for (const pair of init as Iterable<[string, string]>) {
+                    const entry = [...pair]
+                    if (entry.length !== 2) {
+                        throw new TypeError("Each search parameter must be a name and value pair")
+                    }
+                    this.list.push([String(entry[0]), String(entry[1])])
+                }
+            } else {
+                for (const [name, value] of Object.entries(init)) {
+                    this.list.push([name, String(value)])
+                }
+            }
+        }
+
+        public static linkedTo(url: URL, search: string): URLSearchParams {
+            const params = new URLSearchParams(search)
+            params.url = url
+            return params
+        }
+
+        public refresh(search: string) {
+            // @ts-ignore
+            this.list = Deno.core.ops.op_snip_url_parse_search_params(search)
+        }
+
+        private update() {
+            if (this.url !== null) {
+                const query = this.toString()
+                this.url.setComponent("search", query === "" ? "" : `?${query}`, false)
+            }
+        }
+
+        public get size(): number {
+            return this.list.length
+        }
+
+        public append(name: string, value: string) {
+            this.list.push([String(name), String(value)])
+            this.update()
+        }
+
+        public delete(name: string, value?: string) {
+            this.list = this.list.filter(([n, v]) => n !== name || (value !== undefined && v !== String(value)))
+            this.update()
+        }
+
+        public get(name: string): string | null {
+            return this.list.find(([n]) => n === name)?.[1] ?? null
+        }
+
+        public getAll(name: string): string[] {
+            return this.list.filter(([n]) => n === name).map(([, v]) => v)
+        }
+
+        public has(name: string, value?: string): boolean {
+            return this.list.some(([n, v]) => n === name && (value === undefined || v === String(value)))
+        }
+
+        public set(name: string, value: string) {
+            const index = this.list.findIndex(([n]) => n === name)
+            if (index === -1) {
+                this.list.push([String(name), String(value)])
+            } else {
+                this.list[index][1] = String(value)
+                this.list = this.list.filter(([n], i) => n !== name || i === index)
+            }
+            this.update()
+        }
+
+        public sort() {
+            // Array.prototype.sort is stable, so parameters with the same name keep their order
+            this.list.sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0))
+            this.update()
+        }
+
+        public forEach(callback: (value: string, name: string, params: URLSearchParams) => void, thisArg?: unknown) {
+            for (const [name, value] of this.list) {
+                callback.call(thisArg, value, name, this)
+            }
+        }
+
+        public *entries(): IterableIterator<[string, string]> {
+            for (const [name, value] of this.list) {
+                yield [name, value]
+            }
+        }
+
+        public *keys(): IterableIterator<string> {
+            for (const [name] of this.list) {
+                yield name
+            }
+        }
+
+        public *values(): IterableIterator<string> {
+            for (const [, value] of this.list) {
+                yield value
+            }
+        }
+
+        public [Symbol.iterator](): IterableIterator<[string, string]> {
+            return this.entries()
+        }
+
+        public toString(): string {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_url_stringify_search_params(this.list)
+        }
+    }
+
+    function parseUrl(url: string, base?: string | URL): UrlComponents {
+        try {
+            // @ts-ignore
+            return Deno.core.ops.op_snip_url_parse(String(url), base === undefined ? null : String(base))
+        } catch (e) {
+            throw new TypeError((e as Error).message)
+        }
+    }
+
+    class URL {
+        private components: UrlComponents
+        private params: URLSearchParams | null = null
+
+        constructor(url: string | URL, base?: string | URL) {
+            this.components = parseUrl(String(url), base)
+        }
+
+        public static canParse(url: string | URL, base?: string | URL): boolean {
+            try {
+                parseUrl(String(url), base)
+                return true
+            } catch {
+                return false
+            }
+        }
+
+        public setComponent(component: keyof UrlComponents, value: string, refreshParams: boolean = true) {
+            try {
+                // @ts-ignore
+                this.components = Deno.core.ops.op_snip_url_set(this.components.href, component, String(value))
+            } catch (e) {
+                throw new TypeError((e as Error).message)
+            }
+            if (refreshParams && this.params !== null) {
+                this.params.refresh(this.components.search)
+            }
+        }
+
+        public get href(): string { return this.components.href }
+        public set href(value: string) { this.setComponent("href", value) }
+        public get origin(): string { return this.components.origin }
+        public get protocol(): string { return this.components.protocol }
+        public set protocol(value: string) { this.setComponent("protocol", value) }
+        public get username(): string { return this.components.username }
+        public set username(value: string) { this.setComponent("username", value) }
+        public get password(): string { return this.components.password }
+        public set password(value: string) { this.setComponent("password", value) }
+        public get host(): string { return this.components.host }
+        public set host(value: string) { this.setComponent("host", value) }
+        public get hostname(): string { return this.components.hostname }
+        public set hostname(value: string) { this.setComponent("hostname", value) }
+        public get port(): string { return this.components.port }
+        public set port(value: string) { this.setComponent("port", value) }
+        public get pathname(): string { return this.components.pathname }
+        public set pathname(value: string) { this.setComponent("pathname", value) }
+        public get search(): string { return this.components.search }
+        public set search(value: string) { this.setComponent("search", value) }
+        public get hash(): string { return this.components.hash }
+        public set hash(value: string) { this.setComponent("hash", value) }
+
+        public get searchParams(): URLSearchParams {
+            if (this.params === null) {
+                this.params = URLSearchParams.linkedTo(this, this.components.search)
+            }
+            return this.params
+        }
+
+        public toString(): string {
+            return this.components.href
+        }
+
+        public toJSON(): string {
+            return this.components.href
+        }
+    }
+    // @ts-ignore
+    globalThis.URL = URL
+    // @ts-ignore
+    globalThis.URLSearchParams = URLSearchParams
+
+    // Uses V8's serializer, which supports the same values as the structured clone algorithm
+    function structuredClone<T>(value: T): T {
+        try {
+            // @ts-ignore
+            return Deno.core.deserialize(Deno.core.serialize(value))
+        } catch (e) {
+            const error = new Error((e as Error).message)
+            error.name = "DataCloneError"
+            throw error
+        }
+    }
+    // @ts-ignore
+    globalThis.structuredClone = structuredClone
+
+    // Maps timer ids to the resources of their pending sleeps
+    const timers = new Map<number, number>()
+    let nextTimerId = 1
+
+    function startTimer(callback: (...args: unknown[]) => void, delay: unknown, args: unknown[], repeat: boolean): number {
+        if (typeof callback !== "function") {
+            throw new TypeError("Timer callbacks must be functions")
+        }
+        const id = nextTimerId++
+        // @ts-ignore
+        const resourceId: number = Deno.core.ops.op_snip_timer_create()
+        timers.set(id, resourceId)
+        const millis = Math.max(0, Number(delay) || 0)
+        const run = async () => {
+            do {
+                // @ts-ignore
+                const fired: boolean = await Deno.core.ops.op_snip_timer_sleep(resourceId, millis)
+                if (!fired || !timers.has(id)) {
+                    return
+                }
+                if (!repeat) {
+                    clearTimer(id)
+                }
+                callback(...args)
+            } while (repeat && timers.has(id))
+        }
+        run()
+        return id
+    }
+
+    function clearTimer(id: number | undefined) {
+        if (id === undefined) {
+            return
+        }
+        const resourceId = timers.get(id)
+        if (resourceId !== undefined) {
+            timers.delete(id)
+            // @ts-ignore
+            Deno.core.tryClose(resourceId)
+        }
+    }
+
+    // @ts-ignore
+    globalThis.setTimeout = (callback: (...args: unknown[]) => void, delay?: number, ...args: unknown[]) =>
+        startTimer(callback, delay, args, false)
+    // @ts-ignore
+    globalThis.setInterval = (callback: (...args: unknown[]) => void, delay?: number, ...args: unknown[]) =>
+        startTimer(callback, delay, args, true)
+    // @ts-ignore
+    globalThis.clearTimeout = clearTimer
+    // @ts-ignore
+    globalThis.clearInterval = clearTimer
+    // @ts-ignore
+    globalThis.queueMicrotask = (callback: () => void) => {
+        if (typeof callback !== "function") {
+            throw new TypeError("Microtask callbacks must be functions")
+        }
+        Promise.resolve().then(callback)
+    }
+
+    function formatLogArgument(value: unknown): string {
+        if (typeof value === "string") {
+            return value
+        }
+        if (value instanceof Error) {
+            return value.stack ?? `${value.name}: ${value.message}`
+        }
+        if (typeof value === "object" && value !== null) {
+            try {
+                return JSON.stringify(value)
+            } catch {
+                return String(value)
+            }
+        }
+        return String(value)
+    }
+
+    function log(level: "debug" | "info" | "warn" | "error", data: unknown[]) {
+        // @ts-ignore
+        const editorHandle = globalThis?._snipInternals?.editorHandle
+        if (editorHandle === undefined) {
+            return
+        }
+        const message = data.map(formatLogArgument).join(" ")
+        // Logging should not slow down the script, so delivery is not awaited
+        // @ts-ignore
+        Deno.core.ops.snip_op_log(editorHandle, level, message).catch(() => {})
+    }
+
+    const console = {
+        debug: (...data: unknown[]) => log("debug", data),
+        log: (...data: unknown[]) => log("info", data),
+        info: (...data: unknown[]) => log("info", data),
+        warn: (...data: unknown[]) => log("warn", data),
+        error: (...data: unknown[]) => log("error", data),
+    }
+    // @ts-ignore
+    globalThis.console = console
 })()
\ No newline at end of file
@@ -1,17 +1,21 @@
 import { transformActiveTexts } from "lib:@snip/helpers";
 
-function dateToUnix(dateString: string): string {
+function dateToUnix(dateString: string, unit: string): string {
     const date = new Date(dateString);
     if (isNaN(date.getTime())) {
         throw new Error('Invalid date string');
     }
+    if (unit === 'milliseconds') {
+        return date.getTime().toString();
+    }
     return Math.floor(date.getTime() / 1000).toString();
 }
 
 export default async function date_to_unix() {
+    const { unit } = await Editor.getParameters();
     await transformActiveTexts((text: string) => {
         try {
-            return dateToUnix(text);
+            return dateToUnix(text, unit as string);
         } catch (e) {
             throw new Error('Invalid date string');
         }
@@ -2,7 +2,8 @@ import { dump } from "lib:js-yaml";
 import { transformActiveTexts } from "lib:@snip/helpers";
 
 export default async function json_to_yaml() {
-    await transformActiveTexts((text: string) => {
+    const selectionState = await Editor.getSelectionState();
+    const converted = await transformActiveTexts((text: string) => {
         try {
             const jsonObject = JSON.parse(text);
             return dump(jsonObject);
@@ -10,4 +11,8 @@ export default async function json_to_yaml() {
             throw new Error('Invalid JSON');
         }
     });
-}
\ No newline at end of file
+    // Converting the whole document changes its language, converting a selection does not
+    if (converted && !selectionState.hasSelection()) {
+        await Editor.setLanguage("yaml");
+    }
+}
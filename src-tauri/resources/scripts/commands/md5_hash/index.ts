@@ -9,7 +9,7 @@ function generateMd5Hash(text: string): string {
 }
 
 export default async function md5_hash() {
-    await transformActiveTexts((text: string) => {
+    const appended = await transformActiveTexts((text: string) => {
         try {
             const md5Hash = generateMd5Hash(text);
             return `${text}\nMD5 Hash: ${md5Hash}`;
@@ -17,4 +17,7 @@ export default async function md5_hash() {
             throw new Error('Error generating MD5 hash');
         }
     });
-}
\ No newline at end of file
+    if (appended) {
+        await Editor.setStatus("success", "MD5 hash appended", 3000);
+    }
+}
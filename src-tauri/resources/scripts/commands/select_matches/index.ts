@@ -0,0 +1,24 @@
+export default async function select_matches() {
+    const { pattern, ignoreCase } = await Editor.getParameters();
+    let regex: RegExp;
+    try {
+        regex = new RegExp(pattern as string, ignoreCase ? "gi" : "g");
+    } catch (e) {
+        await Editor.setError(String(e));
+        return;
+    }
+
+    const text = await Editor.getFullText();
+    const selections = [];
+    for (const match of text.matchAll(regex)) {
+        // Empty matches would only leave cursors behind
+        if (match[0].length > 0) {
+            selections.push({ anchor: match.index!, head: match.index! + match[0].length });
+        }
+    }
+    if (selections.length === 0) {
+        await Editor.setError("Error: No matches found");
+        return;
+    }
+    await Editor.setSelections(selections);
+}
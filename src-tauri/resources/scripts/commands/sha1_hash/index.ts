@@ -1,20 +1,19 @@
 import { transformActiveTexts } from "lib:@snip/helpers";
-// @ts-ignore
-import Hashes from "lib:jshashes";
 
-function generateSha1Hash(text: string): string {
-    // @ts-ignore
-    const sha1 = new Hashes.SHA1();
-    return sha1.hex(text);
+async function generateSha1Hash(text: string): Promise<string> {
+    return Encoding.encode("hex", await Cryptography.digest("SHA-1", text));
 }
 
 export default async function sha1_hash() {
-    await transformActiveTexts((text: string) => {
+    const appended = await transformActiveTexts(async (text: string) => {
         try {
-            const sha1Hash = generateSha1Hash(text);
+            const sha1Hash = await generateSha1Hash(text);
             return `${text}\nSHA-1 Hash: ${sha1Hash}`;
         } catch (e) {
             throw new Error('Error generating SHA-1 hash');
         }
     });
-}
\ No newline at end of file
+    if (appended) {
+        await Editor.setStatus("success", "SHA-1 hash appended", 3000);
+    }
+}
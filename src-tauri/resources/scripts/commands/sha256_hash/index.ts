@@ -1,20 +1,19 @@
 import { transformActiveTexts } from "lib:@snip/helpers";
-// @ts-ignore
-import Hashes from "lib:jshashes";
 
-function generateSha256Hash(text: string): string {
-    // @ts-ignore
-    const sha256 = new Hashes.SHA256();
-    return sha256.hex(text);
+async function generateSha256Hash(text: string): Promise<string> {
+    return Encoding.encode("hex", await Cryptography.digest("SHA-256", text));
 }
 
 export default async function sha256_hash() {
-    await transformActiveTexts((text: string) => {
+    const appended = await transformActiveTexts(async (text: string) => {
         try {
-            const sha256Hash = generateSha256Hash(text);
+            const sha256Hash = await generateSha256Hash(text);
             return `${text}\nSHA-256 Hash: ${sha256Hash}`;
         } catch (e) {
             throw new Error('Error generating SHA-256 hash');
         }
     });
-}
\ No newline at end of file
+    if (appended) {
+        await Editor.setStatus("success", "SHA-256 hash appended", 3000);
+    }
+}
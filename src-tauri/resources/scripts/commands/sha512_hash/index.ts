@@ -1,20 +1,19 @@
 import { transformActiveTexts } from "lib:@snip/helpers";
-// @ts-ignore
-import Hashes from "lib:jshashes";
 
-function generateSha512Hash(text: string): string {
-    // @ts-ignore
-    const sha512 = new Hashes.SHA512();
-    return sha512.hex(text);
+async function generateSha512Hash(text: string): Promise<string> {
+    return Encoding.encode("hex", await Cryptography.digest("SHA-512", text));
 }
 
 export default async function sha512_hash() {
-    await transformActiveTexts((text: string) => {
+    const appended = await transformActiveTexts(async (text: string) => {
         try {
-            const sha512Hash = generateSha512Hash(text);
+            const sha512Hash = await generateSha512Hash(text);
             return `${text}\nSHA-512 Hash: ${sha512Hash}`;
         } catch (e) {
             throw new Error('Error generating SHA-512 hash');
         }
     });
-}
\ No newline at end of file
+    if (appended) {
+        await Editor.setStatus("success", "SHA-512 hash appended", 3000);
+    }
+}
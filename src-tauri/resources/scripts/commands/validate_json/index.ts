@@ -0,0 +1,168 @@
+class JsonSyntaxError extends Error {
+    readonly position: number;
+
+    constructor(position: number, message: string) {
+        super(message);
+        this.position = position;
+    }
+}
+
+const NUMBER = /-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?/y;
+const ESCAPE = /\\(["\\/bfnrt]|u[0-9a-fA-F]{4})/y;
+
+// Walks through the JSON grammar, since the errors of JSON.parse do not always tell where the
+// problem is
+class JsonScanner {
+    private readonly text: string;
+    private position = 0;
+
+    constructor(text: string) {
+        this.text = text;
+    }
+
+    public scan() {
+        this.value();
+        this.skipWhitespace();
+        if (this.position < this.text.length) {
+            this.fail("Unexpected text after the JSON value");
+        }
+    }
+
+    private fail(message: string): never {
+        if (this.position >= this.text.length) {
+            message = "Unexpected end of input";
+        }
+        throw new JsonSyntaxError(this.position, message);
+    }
+
+    private skipWhitespace() {
+        while (this.position < this.text.length && " \t\n\r".includes(this.text[this.position])) {
+            this.position++;
+        }
+    }
+
+    private expect(character: string, message: string) {
+        this.skipWhitespace();
+        if (this.text[this.position] !== character) {
+            this.fail(message);
+        }
+        this.position++;
+    }
+
+    private value() {
+        this.skipWhitespace();
+        const character = this.text[this.position];
+        if (character === "{") {
+            this.object();
+        } else if (character === "[") {
+            this.array();
+        } else if (character === "\"") {
+            this.string();
+        } else if (!this.literal("true") && !this.literal("false") && !this.literal("null") && !this.number()) {
+            this.fail("Expected a value");
+        }
+    }
+
+    private literal(word: string): boolean {
+        if (!this.text.startsWith(word, this.position)) {
+            return false;
+        }
+        this.position += word.length;
+        return true;
+    }
+
+    private number(): boolean {
+        NUMBER.lastIndex = this.position;
+        const match = NUMBER.exec(this.text);
+        if (!match || match[0].length === 0) {
+            return false;
+        }
+        this.position += match[0].length;
+        return true;
+    }
+
+    private string() {
+        const start = this.position;
+        this.position++;
+        while (this.position < this.text.length) {
+            const character = this.text[this.position];
+            if (character === "\"") {
+                this.position++;
+                return;
+            }
+            if (character === "\\") {
+                ESCAPE.lastIndex = this.position;
+                if (!ESCAPE.test(this.text)) {
+                    this.fail("Invalid escape sequence");
+                }
+                this.position = ESCAPE.lastIndex;
+            } else if (character < " ") {
+                this.fail("Control characters in strings have to be escaped");
+            } else {
+                this.position++;
+            }
+        }
+        throw new JsonSyntaxError(start, "Unterminated string");
+    }
+
+    private object() {
+        this.position++;
+        this.skipWhitespace();
+        if (this.text[this.position] === "}") {
+            this.position++;
+            return;
+        }
+        while (true) {
+            this.skipWhitespace();
+            if (this.text[this.position] !== "\"") {
+                this.fail("Expected a property name");
+            }
+            this.string();
+            this.expect(":", "Expected ':' after the property name");
+            this.value();
+            this.skipWhitespace();
+            if (this.text[this.position] === "}") {
+                this.position++;
+                return;
+            }
+            this.expect(",", "Expected ',' or '}'");
+        }
+    }
+
+    private array() {
+        this.position++;
+        this.skipWhitespace();
+        if (this.text[this.position] === "]") {
+            this.position++;
+            return;
+        }
+        while (true) {
+            this.value();
+            this.skipWhitespace();
+            if (this.text[this.position] === "]") {
+                this.position++;
+                return;
+            }
+            this.expect(",", "Expected ',' or ']'");
+        }
+    }
+}
+
+export default async function validate_json() {
+    const text = await Editor.getFullText();
+    try {
+        new JsonScanner(text).scan();
+    } catch (e) {
+        if (!(e instanceof JsonSyntaxError)) {
+            throw e;
+        }
+        const line = text.slice(0, e.position).split("\n").length;
+        await Editor.setAnnotations({
+            diagnostics: [{ start: e.position, end: e.position + 1, severity: "error", message: e.message }],
+        });
+        await Editor.setStatus("error", `Invalid JSON on line ${line}: ${e.message}`);
+        return;
+    }
+    await Editor.setAnnotations({});
+    await Editor.setStatus("success", "Valid JSON", 3000);
+}
@@ -2,7 +2,8 @@ import { load } from "lib:js-yaml";
 import { transformActiveTexts } from "lib:@snip/helpers";
 
 export default async function yaml_to_json() {
-    await transformActiveTexts((text: string) => {
+    const selectionState = await Editor.getSelectionState();
+    const converted = await transformActiveTexts((text: string) => {
         try {
             const jsonObject = load(text) ?? {};
             return JSON.stringify(jsonObject, null, 2);
@@ -10,4 +11,8 @@ export default async function yaml_to_json() {
             throw new Error('Invalid YAML');
         }
     });
-}
\ No newline at end of file
+    // Converting the whole document changes its language, converting a selection does not
+    if (converted && !selectionState.hasSelection()) {
+        await Editor.setLanguage("json");
+    }
+}
@@ -15,14 +15,87 @@ declare global {
         index: number
         text: string
     }
+    interface DocumentRange {
+        start?: number
+        end?: number
+    }
+    interface ReadChunksOptions extends DocumentRange {
+        chunkSize?: number
+    }
+    interface SelectionRange {
+        anchor: number
+        head?: number
+    }
+    type StatusLevel = "info" | "success" | "warning" | "error"
+    interface Diagnostic {
+        start: number
+        end: number
+        severity: "info" | "warning" | "error"
+        message: string
+    }
+    type Decoration =
+        | { kind: "highlight", start: number, end: number, title?: string }
+        | { kind: "gutterMarker", position: number, text: string, title?: string }
+    interface DocumentAnnotations {
+        diagnostics?: Diagnostic[]
+        decorations?: Decoration[]
+    }
+    interface DocumentInfo {
+        language: string | null
+        length: number
+        lineCount: number
+        filePath: string | null
+        lineEnding: "lf" | "crlf" | "cr"
+    }
     const Editor: {
         getFullText: () => Promise<string>,
         setFullText: (fullText: string) => Promise<void>,
         setError: (error: string) => Promise<void>,
+        setStatus: (level: StatusLevel, message: string, duration?: number) => Promise<void>,
         getPartialText: (start: number, end: number) => Promise<string>
         getSelectionState: () => Promise<SelectionState>
         replaceSelections: (replacements: SelectionReplacement[]) => Promise<void>
+        setSelections: (selections: SelectionRange[], mainSelectionIndex?: number) => Promise<void>
+        insertAt: (position: number, text: string) => Promise<void>
+        replaceRange: (start: number, end: number, text: string) => Promise<void>
+        getCursor: () => Promise<number>
+        getDocumentLength: () => Promise<number>
+        getDocumentInfo: () => Promise<DocumentInfo>
+        setLanguage: (language: string) => Promise<void>
+        setAnnotations: (annotations: DocumentAnnotations) => Promise<void>
+        readChunks: (options?: ReadChunksOptions) => AsyncGenerator<string>
+        readLines: (range?: DocumentRange) => AsyncGenerator<string>
+        appendText: (text: string) => Promise<void>
+        getParameters: () => Promise<Record<string, string | number | boolean>>
+        prompt: (message: string, defaultValue?: string) => Promise<string>
+        confirm: (message: string) => Promise<boolean>
+        pickFromList: (message: string, options: string[]) => Promise<string>
+    }
+    const Files: {
+        readText: (path: string) => Promise<string>
+        writeText: (path: string, text: string) => Promise<void>
+    }
+    type DigestAlgorithm = "SHA-1" | "SHA-256" | "SHA-384" | "SHA-512"
+    type BinaryEncoding = "hex" | "base32" | "base64" | "base64url"
+    type BinaryData = string | ArrayBuffer | ArrayBufferView
+    const Cryptography: {
+        digest: (algorithm: DigestAlgorithm, data: BinaryData) => Promise<Uint8Array>
+        hmac: (algorithm: DigestAlgorithm, key: BinaryData, data: BinaryData) => Promise<Uint8Array>
+        randomBytes: (length: number) => Uint8Array
+    }
+    const Encoding: {
+        encode: (encoding: BinaryEncoding, data: BinaryData) => string
+        decode: (encoding: BinaryEncoding, text: string) => Uint8Array
+        decodeToText: (encoding: BinaryEncoding, text: string) => string
+    }
+    interface Console {
+        debug(...data: any[]): void
+        log(...data: any[]): void
+        info(...data: any[]): void
+        warn(...data: any[]): void
+        error(...data: any[]): void
     }
+    var console: Console
 }
 
 export {}
\ No newline at end of file
@@ -1,4 +1,5 @@
-export default async function transformActiveTexts(transformFunc: (text: string) => string | Promise<string>){
+// Resolves to false when the transformation failed and the error was reported instead
+export default async function transformActiveTexts(transformFunc: (text: string) => string | Promise<string>): Promise<boolean> {
     const selectionState = await Editor.getSelectionState()
     if (selectionState.hasSelection()) {
         const selections = selectionState.getSelections()
@@ -15,7 +16,7 @@ export default async function transformActiveTexts(transformFunc: (text: string)
             )
         } catch (e) {
             await Editor.setError(String(e))
-            return
+            return false
         }
         await Editor.replaceSelections(replacements)
     } else {
@@ -25,8 +26,9 @@ export default async function transformActiveTexts(transformFunc: (text: string)
             result = await transformFunc(source)
         } catch (e) {
             await Editor.setError(String(e))
-            return
+            return false
         }
         await Editor.setFullText(result)
     }
+    return true
 }
\ No newline at end of file
@@ -8,6 +8,13 @@ Deno.test("date_to_unix should convert a date string to a Unix timestamp", async
     await expectOutputForInput(date_to_unix, input, expectedOutput);
 });
 
+Deno.test("date_to_unix should convert a date string to milliseconds", async () => {
+    const input = "October 1, 2021 00:00:00 GMT";
+    const expectedOutput = "1633046400000";
+
+    await expectOutputForInput(date_to_unix, input, expectedOutput, { unit: "milliseconds" });
+});
+
 Deno.test("date_to_unix should handle invalid date string", async () => {
     const input = "invalid_date";
 
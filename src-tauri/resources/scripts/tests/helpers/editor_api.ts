@@ -62,6 +62,46 @@
         text: string
     }
 
+    interface DocumentRange {
+        start?: number
+        end?: number
+    }
+
+    interface ReadChunksOptions extends DocumentRange {
+        chunkSize?: number
+    }
+
+    interface SelectionRange {
+        anchor: number
+        head?: number
+    }
+
+    type StatusLevel = "info" | "success" | "warning" | "error"
+
+    interface Diagnostic {
+        start: number
+        end: number
+        severity: "info" | "warning" | "error"
+        message: string
+    }
+
+    type Decoration =
+        | { kind: "highlight", start: number, end: number, title?: string }
+        | { kind: "gutterMarker", position: number, text: string, title?: string }
+
+    interface DocumentAnnotations {
+        diagnostics?: Diagnostic[]
+        decorations?: Decoration[]
+    }
+
+    interface DocumentInfo {
+        language: string | null
+        length: number
+        lineCount: number
+        filePath: string | null
+        lineEnding: "lf" | "crlf" | "cr"
+    }
+
     class Editor {
         public getFullText(): Promise<string> {
             // @ts-ignore
@@ -80,6 +120,12 @@
             return Promise.resolve()
         }
 
+        public setStatus(level: StatusLevel, message: string, duration?: number) {
+            // @ts-ignore
+            globalThis.editorMock.status = { level, message }
+            return Promise.resolve()
+        }
+
         public getPartialText(start: number, end: number): Promise<string> {
             return Promise.resolve(
                 // @ts-ignore
@@ -96,7 +142,244 @@
             // No op, not supported in mock
             return Promise.resolve()
         }
+
+        // Selections are recorded in editorMock.selections, text positions are not mapped
+        public setSelections(selections: SelectionRange[], mainSelectionIndex: number = 0): Promise<void> {
+            // @ts-ignore
+            globalThis.editorMock.selections = selections.map(({ anchor, head }) => ({ anchor, head: head ?? anchor }))
+            // @ts-ignore
+            globalThis.editorMock.mainSelectionIndex = mainSelectionIndex
+            return Promise.resolve()
+        }
+
+        public insertAt(position: number, text: string): Promise<void> {
+            return this.replaceRange(position, position, text)
+        }
+
+        public replaceRange(start: number, end: number, text: string): Promise<void> {
+            // @ts-ignore
+            const fullText: string = globalThis.editorMock.fullText
+            // @ts-ignore
+            globalThis.editorMock.fullText = fullText.substring(0, Math.min(start, end)) + text + fullText.substring(Math.max(start, end))
+            return Promise.resolve()
+        }
+
+        public getCursor(): Promise<number> {
+            // @ts-ignore
+            const { selections, mainSelectionIndex } = globalThis.editorMock
+            return Promise.resolve(selections?.[mainSelectionIndex ?? 0]?.head ?? 0)
+        }
+
+        public getDocumentLength(): Promise<number> {
+            // @ts-ignore
+            return Promise.resolve(globalThis.editorMock.fullText.length)
+        }
+
+        // The language is taken from editorMock.language, and setLanguage changes it
+        public getDocumentInfo(): Promise<DocumentInfo> {
+            // @ts-ignore
+            const { fullText, language, filePath } = globalThis.editorMock
+            return Promise.resolve({
+                language: language ?? null,
+                length: fullText.length,
+                lineCount: fullText.split("\n").length,
+                filePath: filePath ?? null,
+                lineEnding: "lf",
+            })
+        }
+
+        public setLanguage(language: string): Promise<void> {
+            // @ts-ignore
+            globalThis.editorMock.language = language
+            return Promise.resolve()
+        }
+
+        public setAnnotations(annotations: DocumentAnnotations): Promise<void> {
+            // @ts-ignore
+            globalThis.editorMock.annotations = { diagnostics: [], decorations: [], ...annotations }
+            return Promise.resolve()
+        }
+
+        public async *readChunks(options: ReadChunksOptions = {}): AsyncGenerator<string> {
+            // @ts-ignore
+            const fullText: string = globalThis.editorMock.fullText
+            const end = Math.min(options.end ?? fullText.length, fullText.length)
+            const chunkSize = options.chunkSize ?? 64 * 1024
+            for (let start = options.start ?? 0; start < end; start += chunkSize) {
+                yield fullText.substring(start, Math.min(start + chunkSize, end))
+            }
+        }
+
+        public async *readLines(range: DocumentRange = {}): AsyncGenerator<string> {
+            const text = await this.getPartialText(range.start ?? 0, range.end ?? Infinity)
+            const lines = text.split(/\r?\n/)
+            if (lines[lines.length - 1] === "") {
+                lines.pop()
+            }
+            yield* lines
+        }
+
+        public appendText(text: string): Promise<void> {
+            // @ts-ignore
+            globalThis.editorMock.fullText += text
+            return Promise.resolve()
+        }
+
+        public getParameters(): Promise<Record<string, string | number | boolean>> {
+            // @ts-ignore
+            return Promise.resolve(globalThis.editorMock.parameters ?? {})
+        }
+
+        // Answers are taken from editorMock.answers in the order they are asked for
+        public prompt(message: string, defaultValue?: string): Promise<string> {
+            // @ts-ignore
+            return Promise.resolve(globalThis.editorMock.answers?.shift() ?? defaultValue ?? "")
+        }
+
+        public confirm(message: string): Promise<boolean> {
+            // @ts-ignore
+            return Promise.resolve(globalThis.editorMock.answers?.shift() ?? true)
+        }
+
+        public pickFromList(message: string, options: string[]): Promise<string> {
+            // @ts-ignore
+            return Promise.resolve(globalThis.editorMock.answers?.shift() ?? options[0])
+        }
     }
     // @ts-ignore
     globalThis.Editor = new Editor()
+
+    class Files {
+        public readText(path: string): Promise<string> {
+            // @ts-ignore
+            const text = globalThis.editorMock.files?.[path]
+            if (text === undefined) {
+                return Promise.reject(new Error(`Could not read ${path}`))
+            }
+            return Promise.resolve(text)
+        }
+
+        public writeText(path: string, text: string): Promise<void> {
+            // @ts-ignore
+            globalThis.editorMock.files = { ...globalThis.editorMock.files, [path]: text }
+            return Promise.resolve()
+        }
+    }
+    // @ts-ignore
+    globalThis.Files = new Files()
+
+    type DigestAlgorithm = "SHA-1" | "SHA-256" | "SHA-384" | "SHA-512"
+    type BinaryEncoding = "hex" | "base32" | "base64" | "base64url"
+    type BinaryData = string | ArrayBuffer | ArrayBufferView
+
+    function toBytes(data: BinaryData): Uint8Array {
+        if (typeof data === "string") {
+            return new TextEncoder().encode(data)
+        }
+        if (data instanceof ArrayBuffer) {
+            return new Uint8Array(data)
+        }
+        return new Uint8Array(data.buffer, data.byteOffset, data.byteLength)
+    }
+
+    // Backed by the Web Crypto API of Deno, which computes the same results as the ring ops
+    class Cryptography {
+        public async digest(algorithm: DigestAlgorithm, data: BinaryData): Promise<Uint8Array> {
+            return new Uint8Array(await crypto.subtle.digest(algorithm, toBytes(data)))
+        }
+
+        public async hmac(algorithm: DigestAlgorithm, key: BinaryData, data: BinaryData): Promise<Uint8Array> {
+            const cryptoKey = await crypto.subtle.importKey("raw", toBytes(key), { name: "HMAC", hash: algorithm }, false, ["sign"])
+            return new Uint8Array(await crypto.subtle.sign("HMAC", cryptoKey, toBytes(data)))
+        }
+
+        public randomBytes(length: number): Uint8Array {
+            return crypto.getRandomValues(new Uint8Array(length))
+        }
+    }
+    // @ts-ignore
+    globalThis.Cryptography = new Cryptography()
+
+    const BASE32_ALPHABET = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567"
+
+    function encodeBase32(bytes: Uint8Array): string {
+        let result = ""
+        let buffer = 0
+        let bits = 0
+        for (const byte of bytes) {
+            buffer = (buffer << 8) | byte
+            bits += 8
+            while (bits >= 5) {
+                result += BASE32_ALPHABET[(buffer >> (bits - 5)) & 31]
+                bits -= 5
+            }
+        }
+        if (bits > 0) {
+            result += BASE32_ALPHABET[(buffer << (5 - bits)) & 31]
+        }
+        return result.padEnd(Math.ceil(result.length / 8) * 8, "=")
+    }
+
+    function decodeBase32(text: string): Uint8Array {
+        const bytes = []
+        let buffer = 0
+        let bits = 0
+        for (const char of text.replace(/=+$/, "")) {
+            const value = BASE32_ALPHABET.indexOf(char)
+            if (value === -1) {
+                throw new Error("Invalid base32 input")
+            }
+            buffer = (buffer << 5) | value
+            bits += 5
+            if (bits >= 8) {
+                bytes.push((buffer >> (bits - 8)) & 255)
+                bits -= 8
+            }
+        }
+        return new Uint8Array(bytes)
+    }
+
+    function encodeBase64(bytes: Uint8Array): string {
+        return btoa(String.fromCharCode(...bytes))
+    }
+
+    function decodeBase64(text: string): Uint8Array {
+        return Uint8Array.from(atob(text), (char) => char.charCodeAt(0))
+    }
+
+    class Encoding {
+        public encode(encoding: BinaryEncoding, data: BinaryData): string {
+            const bytes = toBytes(data)
+            switch (encoding) {
+                case "hex":
+                    return Array.from(bytes, (byte) => byte.toString(16).padStart(2, "0")).join("")
+                case "base32":
+                    return encodeBase32(bytes)
+                case "base64":
+                    return encodeBase64(bytes)
+                case "base64url":
+                    return encodeBase64(bytes).replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, "")
+            }
+        }
+
+        public decode(encoding: BinaryEncoding, text: string): Uint8Array {
+            text = text.trim()
+            switch (encoding) {
+                case "hex":
+                    return new Uint8Array((text.match(/../g) ?? []).map((byte) => parseInt(byte, 16)))
+                case "base32":
+                    return decodeBase32(text)
+                case "base64":
+                    return decodeBase64(text)
+                case "base64url":
+                    return decodeBase64(text.replace(/-/g, "+").replace(/_/g, "/"))
+            }
+        }
+
+        public decodeToText(encoding: BinaryEncoding, text: string): string {
+            return new TextDecoder().decode(this.decode(encoding, text))
+        }
+    }
+    // @ts-ignore
+    globalThis.Encoding = new Encoding()
 })()
\ No newline at end of file
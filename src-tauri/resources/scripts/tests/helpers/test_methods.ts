@@ -1,11 +1,12 @@
 import { expect } from "jsr:@std/expect";
 import './editor_api.ts'
 
-export const expectOutputForInput = async (module: () => void | Promise<void>, input: string, expectedOutput: string) => {
+export const expectOutputForInput = async (module: () => void | Promise<void>, input: string, expectedOutput: string, parameters?: Record<string, string | number | boolean>) => {
     // @ts-ignore
     globalThis.editorMock = {
         fullText: input,
         error: undefined,
+        parameters,
     }
 
     await module()
@@ -16,11 +17,12 @@ export const expectOutputForInput = async (module: () => void | Promise<void>, i
     expect(globalThis.editorMock.fullText).toBe(expectedOutput)
 }
 
-export const expectInputGivesError = async (module: () => void | Promise<void>, input: string, expectedErrorOutput: string) => {
+export const expectInputGivesError = async (module: () => void | Promise<void>, input: string, expectedErrorOutput: string, parameters?: Record<string, string | number | boolean>) => {
     // @ts-ignore
     globalThis.editorMock = {
         fullText: input,
         error: undefined,
+        parameters,
     }
 
     await module()
@@ -1,3 +1,4 @@
+import { expect } from "jsr:@std/expect";
 import { expectOutputForInput, expectInputGivesError } from "./helpers/test_methods.ts";
 import json_to_yaml from "../commands/json_to_yaml";
 
@@ -60,4 +61,18 @@ boolean: true
 `;
 
     await expectOutputForInput(json_to_yaml, input, expectedOutput);
-});
\ No newline at end of file
+});
+
+Deno.test("convert_json_to_yaml should switch the language to YAML", async () => {
+    await expectOutputForInput(json_to_yaml, '{"name":"John"}', "name: John\n");
+
+    // @ts-ignore
+    expect(globalThis.editorMock.language).toBe("yaml");
+});
+
+Deno.test("convert_json_to_yaml should keep the language on error", async () => {
+    await expectInputGivesError(json_to_yaml, '{"name":"John"', "Error: Invalid JSON");
+
+    // @ts-ignore
+    expect(globalThis.editorMock.language).toBe(undefined);
+});
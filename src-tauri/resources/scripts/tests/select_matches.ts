@@ -0,0 +1,26 @@
+import { expect } from "jsr:@std/expect";
+import { expectInputGivesError } from "./helpers/test_methods.ts";
+import select_matches from "../commands/select_matches";
+
+Deno.test("select_matches should select every match", async () => {
+    // @ts-ignore
+    globalThis.editorMock = {
+        fullText: "foo bar foo baz",
+        error: undefined,
+        parameters: { pattern: "fo+", ignoreCase: false },
+    };
+
+    await select_matches();
+
+    // @ts-ignore
+    expect(globalThis.editorMock.error).toBe(undefined);
+    // @ts-ignore
+    expect(globalThis.editorMock.selections).toEqual([
+        { anchor: 0, head: 3 },
+        { anchor: 8, head: 11 },
+    ]);
+});
+
+Deno.test("select_matches should error when nothing matches", async () => {
+    await expectInputGivesError(select_matches, "foo bar", "Error: No matches found", { pattern: "baz", ignoreCase: false });
+});
@@ -1,3 +1,4 @@
+import { expect } from "jsr:@std/expect";
 import { expectOutputForInput, expectInputGivesError } from "./helpers/test_methods.ts";
 import sha256_hash from "../commands/sha256_hash";
 
@@ -13,4 +14,14 @@ Deno.test("sha256_hash should handle empty input", async () => {
     const expectedOutput = `\nSHA-256 Hash: e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855`;
 
     await expectOutputForInput(sha256_hash, input, expectedOutput);
-});
\ No newline at end of file
+});
+
+Deno.test("sha256_hash should report that the hash was appended", async () => {
+    const input = "Hello, world!";
+    const expectedOutput = `Hello, world!\nSHA-256 Hash: 315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3`;
+
+    await expectOutputForInput(sha256_hash, input, expectedOutput);
+
+    // @ts-ignore
+    expect(globalThis.editorMock.status).toEqual({ level: "success", message: "SHA-256 hash appended" });
+});
@@ -0,0 +1,41 @@
+import { expect } from "jsr:@std/expect";
+import { expectOutputForInput } from "./helpers/test_methods.ts";
+import validate_json from "../commands/validate_json";
+
+Deno.test("validate_json should clear the diagnostics of valid JSON", async () => {
+    const input = '{"name": "John", "tags": ["a", "b"], "age": 30.5e1}';
+
+    await expectOutputForInput(validate_json, input, input);
+
+    // @ts-ignore
+    expect(globalThis.editorMock.annotations.diagnostics).toEqual([]);
+    // @ts-ignore
+    expect(globalThis.editorMock.status).toEqual({ level: "success", message: "Valid JSON" });
+});
+
+Deno.test("validate_json should mark the position of the problem", async () => {
+    const input = '{\n  "name": "John",\n  "age": 30,\n}';
+
+    await expectOutputForInput(validate_json, input, input);
+
+    // @ts-ignore
+    expect(globalThis.editorMock.annotations.diagnostics).toEqual([
+        { start: 33, end: 34, severity: "error", message: "Expected a property name" },
+    ]);
+    // @ts-ignore
+    expect(globalThis.editorMock.status).toEqual({
+        level: "error",
+        message: "Invalid JSON on line 4: Expected a property name",
+    });
+});
+
+Deno.test("validate_json should point unterminated strings to their start", async () => {
+    const input = '["a", "b]';
+
+    await expectOutputForInput(validate_json, input, input);
+
+    // @ts-ignore
+    expect(globalThis.editorMock.annotations.diagnostics[0].start).toBe(6);
+    // @ts-ignore
+    expect(globalThis.editorMock.annotations.diagnostics[0].message).toBe("Unterminated string");
+});
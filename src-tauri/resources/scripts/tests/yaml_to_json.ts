@@ -1,3 +1,4 @@
+import { expect } from "jsr:@std/expect";
 import { expectOutputForInput, expectInputGivesError } from "./helpers/test_methods.ts";
 import yaml_to_json from "../commands/yaml_to_json";
 
@@ -83,4 +84,21 @@ null: null`;
 }`;
 
     await expectOutputForInput(yaml_to_json, input, expectedOutput);
-});
\ No newline at end of file
+});
+
+Deno.test("convert_yaml_to_json should switch the language to JSON", async () => {
+    await expectOutputForInput(yaml_to_json, "name: John", `{
+  "name": "John"
+}`);
+
+    // @ts-ignore
+    expect(globalThis.editorMock.language).toBe("json");
+});
+
+Deno.test("convert_yaml_to_json should keep the language on error", async () => {
+    await expectInputGivesError(yaml_to_json, `name: John
+city New York`, "Error: Invalid YAML");
+
+    // @ts-ignore
+    expect(globalThis.editorMock.language).toBe(undefined);
+});
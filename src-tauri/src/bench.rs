@@ -0,0 +1,150 @@
+use crate::cli::{has_granted_permissions, load_scripts, load_stored_settings, run_in_memory};
+use crate::scripts::loader::parameters::{resolve_parameter_values, ParameterValues};
+use crate::scripts::runtime::ScriptRuntime;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::runtime;
+
+pub const BENCH_SUBCOMMAND: &str = "bench";
+const BENCH_USAGE: &str = "Usage: snip bench [--runs <count>] [--input <text>] <command>
+
+Measures how long it takes to run a script command, starting from a runtime created from
+source, a runtime created from the startup snapshot and a runtime that was prepared in advance.";
+const DEFAULT_BENCH_RUNS: usize = 20;
+
+/// Time a single run may take in `snip bench`, since it does not read the settings
+const BENCH_RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct ScriptBenchArguments {
+    command: String,
+    runs: usize,
+    input: String,
+}
+
+pub fn parse_script_bench_arguments(args: &[String]) -> Result<ScriptBenchArguments, String> {
+    let mut command = None;
+    let mut runs = DEFAULT_BENCH_RUNS;
+    let mut input = String::new();
+    let mut remaining = args.iter().skip(2);
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Err(BENCH_USAGE.to_string()),
+            "--runs" => {
+                match remaining
+                    .next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                {
+                    Some(count) if count > 0 => runs = count,
+                    _ => {
+                        return Err(format!(
+                            "--runs expects a positive number\n\n{}",
+                            BENCH_USAGE
+                        ))
+                    }
+                }
+            }
+            "--input" => match remaining.next() {
+                Some(text) => input = text.clone(),
+                None => return Err(format!("--input expects a text\n\n{}", BENCH_USAGE)),
+            },
+            _ if command.is_none() => command = Some(arg.clone()),
+            _ => return Err(format!("Unexpected argument: {}\n\n{}", arg, BENCH_USAGE)),
+        }
+    }
+
+    command
+        .map(|command| ScriptBenchArguments {
+            command,
+            runs,
+            input,
+        })
+        .ok_or_else(|| BENCH_USAGE.to_string())
+}
+
+fn print_bench_results(name: &str, mut durations: Vec<Duration>) {
+    durations.sort();
+    let total: Duration = durations.iter().sum();
+    let mean = total / durations.len() as u32;
+    let median = durations[durations.len() / 2];
+    println!(
+        "{:<10} mean {:>8.2} ms   median {:>8.2} ms   min {:>8.2} ms   max {:>8.2} ms",
+        name,
+        mean.as_secs_f64() * 1000.0,
+        median.as_secs_f64() * 1000.0,
+        durations[0].as_secs_f64() * 1000.0,
+        durations[durations.len() - 1].as_secs_f64() * 1000.0,
+    );
+}
+
+/// Runs a script command repeatedly and prints how long the runs took for each way of creating
+/// a runtime:
+/// - cold: the runtime is created from source for every run
+/// - snapshot: the runtime is created from the startup snapshot for every run
+/// - warm: the runtime was prepared before the run started, like windows do
+pub fn bench_script_from_cli(
+    arguments: ScriptBenchArguments,
+    bundled_script_directory: PathBuf,
+    identifier: &str,
+) -> i32 {
+    let settings = load_stored_settings(identifier);
+
+    let rt = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Could not create tokio runtime");
+    rt.block_on(async move {
+        let script_manager = load_scripts(&settings, bundled_script_directory, identifier).await;
+        let Some(command) = script_manager.resolve_command(&arguments.command).cloned() else {
+            eprintln!(
+                "Could not find a command matching \"{}\"",
+                arguments.command
+            );
+            return 2;
+        };
+        if !has_granted_permissions(&settings, &command) {
+            return 2;
+        }
+        // Runs use the default values of the parameters
+        let parameters =
+            match resolve_parameter_values(&command.info.parameters, ParameterValues::new()) {
+                Ok(parameters) => parameters,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return 2;
+                }
+            };
+        let libraries = script_manager.get_libraries_snapshot();
+        println!("Running {} {} times per mode", command.id, arguments.runs);
+
+        for mode in ["cold", "snapshot", "warm"] {
+            let mut durations = Vec::with_capacity(arguments.runs);
+            for _ in 0..arguments.runs {
+                let prepared_runtime = (mode == "warm").then(ScriptRuntime::from_snapshot);
+                let started_at = Instant::now();
+                let runtime = match (mode, prepared_runtime) {
+                    (_, Some(runtime)) => runtime,
+                    ("cold", _) => ScriptRuntime::from_source(),
+                    _ => ScriptRuntime::from_snapshot(),
+                };
+                let result = run_in_memory(
+                    command.clone(),
+                    libraries.clone(),
+                    parameters.clone(),
+                    arguments.input.clone(),
+                    BENCH_RUN_TIMEOUT,
+                    runtime,
+                )
+                .await;
+                durations.push(started_at.elapsed());
+                if let Err(errors) = result {
+                    for error in errors {
+                        eprintln!("{}", error);
+                    }
+                    return 1;
+                }
+            }
+            print_bench_results(mode, durations);
+        }
+        0
+    })
+}
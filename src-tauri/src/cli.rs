@@ -0,0 +1,422 @@
+#[cfg(feature = "bench")]
+use crate::bench::{
+    bench_script_from_cli, parse_script_bench_arguments, ScriptBenchArguments, BENCH_SUBCOMMAND,
+};
+use crate::document::{load_or_create_document, untitled_document, OpenedDocument};
+use crate::scripts::abort::ScriptAbortHandle;
+use crate::scripts::commands::{
+    handle_script_run, InternalScriptRunEditorRequest, ScriptRun, ScriptRunEditorRequestEvent,
+};
+use crate::scripts::loader::parameters::{resolve_parameter_values, ParameterValues};
+use crate::scripts::loader::scripts::{Command, Libraries, ScriptManager};
+use crate::scripts::loader::transpile_cache::{init_transpile_cache, TRANSPILE_CACHE_DIRECTORY};
+use crate::scripts::locations::{load_script_locations, USER_SCRIPT_DIRECTORY};
+use crate::scripts::memory_editor::MemoryEditor;
+use crate::scripts::runtime::ScriptRuntime;
+use crate::settings::Settings;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::runtime;
+use tokio::sync::mpsc::channel;
+
+const STDIN_ARGUMENT: &str = "-";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LaunchTarget {
+    File(PathBuf),
+    Stdin,
+}
+
+/// Extracts the documents to open from the arguments Snip was started with. The first argument
+/// is expected to be the executable. Relative paths are resolved against `cwd`, since arguments
+/// forwarded from a second instance were given relative to the working directory of that
+/// instance. Options (anything starting with a dash, such as the `-psn_` argument macOS passes)
+/// are skipped, unless they follow a `--` separator.
+pub fn parse_launch_targets(args: &[String], cwd: &Path) -> Vec<LaunchTarget> {
+    let mut targets = vec![];
+    let mut only_paths = false;
+    for arg in args.iter().skip(1) {
+        if !only_paths {
+            if arg == "--" {
+                only_paths = true;
+                continue;
+            }
+            if arg == STDIN_ARGUMENT {
+                targets.push(LaunchTarget::Stdin);
+                continue;
+            }
+            if arg.starts_with('-') {
+                continue;
+            }
+        }
+        targets.push(LaunchTarget::File(cwd.join(arg)));
+    }
+    targets
+}
+
+pub fn read_stdin() -> Option<String> {
+    let mut text = String::new();
+    match std::io::stdin().read_to_string(&mut text) {
+        Ok(_) => Some(text),
+        Err(e) => {
+            eprintln!("Could not read from stdin: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Loads the document of every launch target. Only the first `-` argument receives the contents
+/// of `stdin`. When there are no targets (or none of them could be loaded), a single `None` is
+/// returned to open an empty window.
+pub fn load_launch_documents(
+    targets: Vec<LaunchTarget>,
+    mut stdin: Option<String>,
+) -> Vec<Option<OpenedDocument>> {
+    let mut documents = Vec::new();
+    for target in targets {
+        let document = match target {
+            LaunchTarget::File(path) => match load_or_create_document(path) {
+                Ok(document) => document,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            },
+            LaunchTarget::Stdin => untitled_document(stdin.take().unwrap_or_default()),
+        };
+        documents.push(Some(document));
+    }
+
+    if documents.is_empty() {
+        documents.push(None);
+    }
+    documents
+}
+
+const RUN_SUBCOMMAND: &str = "run";
+const RUN_USAGE: &str =
+    "Usage: snip run [--timeout <seconds>] [--param <name>=<value>]... <command>
+
+Runs a script command against the text read from stdin and writes the result to stdout.
+The command can be given by its id, its name or (part of) its title. Parameters that are not
+given use their default value.";
+
+pub struct ScriptRunArguments {
+    command: String,
+    timeout: Option<Duration>,
+    parameters: Vec<(String, String)>,
+}
+
+pub enum CliSubcommand {
+    Run(ScriptRunArguments),
+    #[cfg(feature = "bench")]
+    Bench(ScriptBenchArguments),
+}
+
+/// Release builds on Windows start without a console, so subcommands could not write to the
+/// terminal they were started from. Attaches to the console of that terminal instead.
+#[cfg(windows)]
+pub fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // Fails when Snip was not started from a console, in which case there is nothing to write to
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Detects whether Snip was started as `snip run ...`, or `snip bench ...` in builds with the
+/// `bench` feature, in which case no windows should be opened. Returns the parsed arguments, or a usage message when they are
+/// invalid.
+pub fn parse_cli_subcommand(args: &[String]) -> Option<Result<CliSubcommand, String>> {
+    match args.get(1).map(String::as_str) {
+        Some(RUN_SUBCOMMAND) => Some(parse_script_run_arguments(args).map(CliSubcommand::Run)),
+        #[cfg(feature = "bench")]
+        Some(BENCH_SUBCOMMAND) => {
+            Some(parse_script_bench_arguments(args).map(CliSubcommand::Bench))
+        }
+        _ => None,
+    }
+}
+
+/// Runs a subcommand and returns the exit code for the process
+pub fn run_cli_subcommand(
+    subcommand: CliSubcommand,
+    bundled_script_directory: PathBuf,
+    identifier: &str,
+) -> i32 {
+    match subcommand {
+        CliSubcommand::Run(arguments) => {
+            run_script_from_cli(arguments, bundled_script_directory, identifier)
+        }
+        #[cfg(feature = "bench")]
+        CliSubcommand::Bench(arguments) => {
+            bench_script_from_cli(arguments, bundled_script_directory, identifier)
+        }
+    }
+}
+
+fn parse_script_run_arguments(args: &[String]) -> Result<ScriptRunArguments, String> {
+    let mut command = None;
+    let mut timeout = None;
+    let mut parameters = vec![];
+    let mut remaining = args.iter().skip(2);
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Err(RUN_USAGE.to_string()),
+            "--timeout" => {
+                let seconds = remaining.next().and_then(|value| value.parse::<u64>().ok());
+                match seconds {
+                    Some(seconds) if seconds > 0 => timeout = Some(Duration::from_secs(seconds)),
+                    _ => {
+                        return Err(format!(
+                            "--timeout expects a positive number of seconds\n\n{}",
+                            RUN_USAGE
+                        ))
+                    }
+                }
+            }
+            "--param" => match remaining.next().and_then(|value| value.split_once('=')) {
+                Some((name, value)) => parameters.push((name.to_string(), value.to_string())),
+                None => {
+                    return Err(format!(
+                        "--param expects a value like name=value\n\n{}",
+                        RUN_USAGE
+                    ))
+                }
+            },
+            _ if command.is_none() => command = Some(arg.clone()),
+            _ => return Err(format!("Unexpected argument: {}\n\n{}", arg, RUN_USAGE)),
+        }
+    }
+
+    command
+        .map(|command| ScriptRunArguments {
+            command,
+            timeout,
+            parameters,
+        })
+        .ok_or_else(|| RUN_USAGE.to_string())
+}
+
+/// Reads the settings stored by the app, without the store plugin that needs a running app. The
+/// store plugin keeps its files in the app data directory.
+pub fn load_stored_settings(identifier: &str) -> Settings {
+    let stored_settings = dirs::data_dir()
+        .map(|data_dir| data_dir.join(identifier).join("settings.json"))
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|contents| serde_json::from_slice::<serde_json::Value>(&contents).ok())
+        .and_then(|mut store| store.get_mut("settings").map(serde_json::Value::take));
+    match stored_settings {
+        Some(stored_settings) => serde_json::from_value(stored_settings).unwrap_or_default(),
+        None => Settings::default(),
+    }
+}
+
+/// Loads the scripts from the same locations as the app does
+pub async fn load_scripts(
+    settings: &Settings,
+    bundled_script_directory: PathBuf,
+    identifier: &str,
+) -> ScriptManager {
+    // Mirrors the app config directory used by the app
+    let user_script_directory = dirs::config_dir()
+        .map(|config_dir| config_dir.join(identifier).join(USER_SCRIPT_DIRECTORY))
+        .filter(|directory| directory.is_dir());
+
+    // Mirrors the app cache directory, so the app and the command line share transpiled modules
+    if let Some(cache_dir) = dirs::cache_dir() {
+        init_transpile_cache(cache_dir.join(identifier).join(TRANSPILE_CACHE_DIRECTORY));
+    }
+
+    let mut script_manager = ScriptManager::new();
+    load_script_locations(
+        &mut script_manager,
+        bundled_script_directory,
+        settings.script_locations(),
+        user_script_directory,
+    )
+    .await;
+    script_manager
+}
+
+/// There is no way to ask for consent from the command line, so commands that access files have
+/// to be allowed in the app first
+pub fn has_granted_permissions(settings: &Settings, command: &Command) -> bool {
+    if settings.has_granted_permissions(command) {
+        return true;
+    }
+    eprintln!(
+        "{} needs access to files. Run it once in Snip to allow this.",
+        command.id
+    );
+    false
+}
+
+/// Converts the parameters given on the command line to the types the command declares
+fn parse_cli_parameters(
+    command: &Command,
+    arguments: &[(String, String)],
+) -> Result<ParameterValues, String> {
+    let mut values = ParameterValues::new();
+    for (name, value) in arguments {
+        let parameter = command
+            .info
+            .parameters
+            .iter()
+            .find(|parameter| &parameter.name == name)
+            .ok_or_else(|| format!("{} has no parameter \"{}\"", command.id, name))?;
+        values.insert(name.clone(), parameter.parse_value(value)?);
+    }
+    resolve_parameter_values(&command.info.parameters, values)
+}
+
+/// Runs a command against a document in memory. Returns the resulting text, or the errors
+/// reported by the script.
+pub async fn run_in_memory(
+    command: Command,
+    libraries: Libraries,
+    parameters: ParameterValues,
+    input: String,
+    timeout: Duration,
+    runtime: ScriptRuntime,
+) -> Result<String, Vec<String>> {
+    let mut editor = MemoryEditor::new(input);
+    let (sender, mut receiver) = channel::<InternalScriptRunEditorRequest>(1);
+    let run = ScriptRun {
+        command,
+        libraries,
+        parameters,
+        editor_request_channel: sender,
+        editor_state: editor.editor_state(),
+        abort_handle: ScriptAbortHandle::new(),
+        timeout,
+    };
+
+    let mut errors = vec![];
+    let serve_editor = async {
+        while let Some(request) = receiver.recv().await {
+            match editor.handle_request(request) {
+                Some(InternalScriptRunEditorRequest::Request(
+                    ScriptRunEditorRequestEvent::SetError(error),
+                )) => errors.push(error),
+                Some(InternalScriptRunEditorRequest::Error(error)) => {
+                    errors.push(error.to_string())
+                }
+                // Logging goes to stderr, so it does not end up in the result
+                Some(InternalScriptRunEditorRequest::Request(
+                    ScriptRunEditorRequestEvent::Log(entry),
+                )) => eprintln!("[{}] {}", entry.level.as_str(), entry.message),
+                Some(InternalScriptRunEditorRequest::Request(
+                    ScriptRunEditorRequestEvent::SetStatus { level, message, .. },
+                )) => eprintln!("[{}] {}", level.as_str(), message),
+                // Decorations only mean something in the editor, diagnostics are listed
+                Some(InternalScriptRunEditorRequest::Request(
+                    ScriptRunEditorRequestEvent::SetAnnotations(annotations),
+                )) => {
+                    for diagnostic in annotations.diagnostics {
+                        let (line, column) = editor.line_and_column(diagnostic.start);
+                        eprintln!(
+                            "{}:{}: {}: {}",
+                            line,
+                            column,
+                            diagnostic.severity.as_str(),
+                            diagnostic.message
+                        );
+                    }
+                }
+                Some(InternalScriptRunEditorRequest::RequestWithResponse {
+                    event,
+                    reply_sender,
+                }) => {
+                    let _ = reply_sender.send(Err(format!(
+                        "{:?} is not supported when running from the command line",
+                        event
+                    )));
+                }
+                _ => {}
+            }
+        }
+    };
+    tokio::join!(handle_script_run(run, runtime), serve_editor);
+
+    if errors.is_empty() {
+        Ok(editor.into_text())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Runs a script command against stdin without starting the user interface. Scripts are loaded
+/// from the same locations as in the app. Returns the exit code for the process: 0 on success, 1
+/// when the script reported an error and 2 when the command could not be started.
+fn run_script_from_cli(
+    arguments: ScriptRunArguments,
+    bundled_script_directory: PathBuf,
+    identifier: &str,
+) -> i32 {
+    let settings = load_stored_settings(identifier);
+
+    let rt = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Could not create tokio runtime");
+    rt.block_on(async move {
+        let script_manager = load_scripts(&settings, bundled_script_directory, identifier).await;
+
+        let Some(command) = script_manager.resolve_command(&arguments.command).cloned() else {
+            eprintln!(
+                "Could not find a command matching \"{}\"",
+                arguments.command
+            );
+            return 2;
+        };
+        if !has_granted_permissions(&settings, &command) {
+            return 2;
+        }
+        let parameters = match parse_cli_parameters(&command, &arguments.parameters) {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        };
+        let libraries = script_manager.get_libraries_snapshot();
+
+        let Some(input) = read_stdin() else {
+            return 2;
+        };
+        let timeout = arguments
+            .timeout
+            .unwrap_or_else(|| settings.script_timeout());
+        let result = run_in_memory(
+            command,
+            libraries,
+            parameters,
+            input,
+            timeout,
+            ScriptRuntime::from_snapshot(),
+        )
+        .await;
+
+        let text = match result {
+            Ok(text) => text,
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+                return 1;
+            }
+        };
+
+        let mut stdout = std::io::stdout();
+        if let Err(e) = stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush())
+        {
+            eprintln!("Could not write result: {:?}", e);
+            return 1;
+        }
+        0
+    })
+}
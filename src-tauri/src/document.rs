@@ -0,0 +1,497 @@
+use crate::window::{open_window, Windows};
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use tauri::async_runtime::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FilePath, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::oneshot;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    /// Picks the line ending that occurs most often in the given text, preferring LF when the
+    /// text has no line breaks at all.
+    fn detect(text: &str) -> LineEnding {
+        let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    crlf += 1;
+                }
+                '\r' => cr += 1,
+                '\n' => lf += 1,
+                _ => {}
+            }
+        }
+        if crlf > lf && crlf >= cr {
+            LineEnding::Crlf
+        } else if cr > lf && cr > crlf {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+/// How a file was encoded on disk, so saving it again keeps the original byte layout.
+#[derive(Clone, Copy, Debug)]
+pub struct TextFormat {
+    bom: bool,
+    line_ending: LineEnding,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            bom: false,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DocumentFile {
+    path: PathBuf,
+    format: TextFormat,
+}
+
+pub struct OpenedDocument {
+    file: Option<DocumentFile>,
+    text: String,
+}
+
+pub struct DocumentState {
+    untitled_index: u32,
+    file: Option<DocumentFile>,
+    dirty: bool,
+    /// The latest editor revision the window reported, used to tell whether a finished save
+    /// still covers all edits
+    revision: u64,
+    pending_text: Option<String>,
+}
+
+impl DocumentState {
+    pub fn new(untitled_index: u32, document: Option<OpenedDocument>) -> Self {
+        let (file, pending_text) = match document {
+            Some(document) => (document.file, Some(document.text)),
+            None => (None, None),
+        };
+        // Untitled documents that start out with text (e.g. read from stdin) are unsaved changes
+        let dirty = file.is_none() && pending_text.as_ref().is_some_and(|text| !text.is_empty());
+        Self {
+            untitled_index,
+            file,
+            dirty,
+            revision: 0,
+            pending_text,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        let name = match &self.file {
+            Some(file) => file
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.path.display().to_string()),
+            None => format!("Untitled {}", self.untitled_index),
+        };
+        format!("Snip - {}{}", name, if self.dirty { "*" } else { "" })
+    }
+
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// The line ending the document is saved with. Untitled documents are saved with LF.
+    pub fn line_ending(&self) -> LineEnding {
+        self.file
+            .as_ref()
+            .map_or(LineEnding::Lf, |file| file.format.line_ending)
+    }
+
+    /// An untitled document without any edits, which can be replaced by an opened file instead of
+    /// opening a new window for it.
+    pub fn is_pristine(&self) -> bool {
+        self.file.is_none() && !self.dirty && self.pending_text.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentContents {
+    text: String,
+    path: Option<String>,
+}
+
+/// Reads a UTF-8 file, stripping the byte order mark and normalizing line endings to LF, which is
+/// what the editor works with.
+pub fn read_text_file(path: &Path) -> Result<(String, TextFormat), Error> {
+    let bytes = std::fs::read(path)?;
+    let (bom, bytes) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(stripped) => (true, stripped.to_vec()),
+        None => (false, bytes),
+    };
+    let text = String::from_utf8(bytes).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is not a valid UTF-8 text file", path.display()),
+        )
+    })?;
+    let line_ending = LineEnding::detect(&text);
+    let text = if line_ending == LineEnding::Lf {
+        text
+    } else {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    };
+    Ok((text, TextFormat { bom, line_ending }))
+}
+
+pub fn write_text_file(path: &Path, text: &str, format: TextFormat) -> Result<(), Error> {
+    let mut bytes = Vec::with_capacity(text.len() + UTF8_BOM.len());
+    if format.bom {
+        bytes.extend_from_slice(UTF8_BOM);
+    }
+    if format.line_ending == LineEnding::Lf {
+        bytes.extend_from_slice(text.as_bytes());
+    } else {
+        bytes.extend_from_slice(text.replace('\n', format.line_ending.as_str()).as_bytes());
+    }
+    std::fs::write(path, bytes)
+}
+
+pub fn load_document(path: PathBuf) -> Result<OpenedDocument, String> {
+    let (text, format) =
+        read_text_file(&path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    Ok(OpenedDocument {
+        file: Some(DocumentFile { path, format }),
+        text,
+    })
+}
+
+/// Like [load_document], but a path that does not exist yet results in an empty document that
+/// will be created on the first save.
+pub fn load_or_create_document(path: PathBuf) -> Result<OpenedDocument, String> {
+    if path.exists() {
+        return load_document(path);
+    }
+    Ok(OpenedDocument {
+        file: Some(DocumentFile {
+            path,
+            format: Default::default(),
+        }),
+        text: String::new(),
+    })
+}
+
+pub fn untitled_document(text: String) -> OpenedDocument {
+    OpenedDocument { file: None, text }
+}
+
+pub fn update_window_title(app: &AppHandle, window_label: &str, document: &DocumentState) {
+    if let Some(window) = app.get_webview_window(window_label) {
+        if let Err(e) = window.set_title(&document.title()) {
+            log::warn!("Could not update title of window {}: {:?}", window_label, e);
+        }
+    }
+}
+
+fn show_error(app: &AppHandle, title: &str, message: String) {
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// Opens the file at `path` in the given window when that window is still pristine, or in a new
+/// window otherwise.
+pub async fn open_file(
+    app: &AppHandle,
+    path: PathBuf,
+    target_window: Option<&str>,
+) -> Result<(), String> {
+    let mut document = load_document(path)?;
+
+    let windows: State<'_, Mutex<Windows>> = app.state();
+    let mut windows = windows.lock().await;
+    let target_window = target_window.filter(|window_label| {
+        windows
+            .get_document_state(window_label)
+            .is_some_and(|document_state| document_state.is_pristine())
+    });
+
+    if let Some(window_label) = target_window {
+        let document_state = windows
+            .get_document_state(window_label)
+            .expect("Window should have document state");
+        let contents = DocumentContents {
+            text: std::mem::take(&mut document.text),
+            path: document
+                .file
+                .as_ref()
+                .map(|file| file.path.display().to_string()),
+        };
+        document_state.file = document.file;
+        document_state.dirty = false;
+        update_window_title(app, window_label, document_state);
+        app.emit_to(window_label, "document_opened", contents)
+            .map_err(|e| e.to_string())?;
+    } else {
+        drop(windows);
+        open_window(app, Some(document))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+async fn pick_file_to_open(app: &AppHandle, window_label: &str) -> Option<PathBuf> {
+    let (sender, receiver) = oneshot::channel::<Option<FilePath>>();
+    let mut dialog = app.dialog().file();
+    if let Some(window) = app.get_webview_window(window_label) {
+        dialog = dialog.set_parent(&window);
+    }
+    dialog.pick_file(move |path| {
+        let _ = sender.send(path);
+    });
+    receiver.await.ok().flatten()?.into_path().ok()
+}
+
+async fn pick_file_to_save(
+    app: &AppHandle,
+    window_label: &str,
+    current_path: Option<&Path>,
+) -> Option<PathBuf> {
+    let (sender, receiver) = oneshot::channel::<Option<FilePath>>();
+    let mut dialog = app.dialog().file();
+    if let Some(window) = app.get_webview_window(window_label) {
+        dialog = dialog.set_parent(&window);
+    }
+    if let Some(current_path) = current_path {
+        if let Some(directory) = current_path.parent() {
+            dialog = dialog.set_directory(directory);
+        }
+        if let Some(file_name) = current_path.file_name() {
+            dialog = dialog.set_file_name(file_name.to_string_lossy());
+        }
+    }
+    dialog.save_file(move |path| {
+        let _ = sender.send(path);
+    });
+    receiver.await.ok().flatten()?.into_path().ok()
+}
+
+pub async fn confirm(app: &AppHandle, title: &str, message: String, ok_label: &str) -> bool {
+    let (sender, receiver) = oneshot::channel::<bool>();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            ok_label.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            let _ = sender.send(confirmed);
+        });
+    receiver.await.unwrap_or(false)
+}
+
+pub async fn open_file_from_dialog(app: AppHandle, window_label: String) {
+    let Some(path) = pick_file_to_open(&app, &window_label).await else {
+        return;
+    };
+    if let Err(e) = open_file(&app, path, Some(&window_label)).await {
+        show_error(&app, "Could not open file", e);
+    }
+}
+
+pub async fn revert_document_in_window(app: AppHandle, window_label: String) {
+    let (path, dirty) = {
+        let windows: State<'_, Mutex<Windows>> = app.state();
+        let mut windows = windows.lock().await;
+        let Some(document_state) = windows.get_document_state(&window_label) else {
+            return;
+        };
+        let Some(file) = &document_state.file else {
+            return;
+        };
+        (file.path.clone(), document_state.dirty)
+    };
+
+    if dirty
+        && !confirm(
+            &app,
+            "Revert",
+            format!(
+                "Discard all unsaved changes and reload {} from disk?",
+                path.display()
+            ),
+            "Revert",
+        )
+        .await
+    {
+        return;
+    }
+
+    let document = match load_document(path) {
+        Ok(document) => document,
+        Err(e) => {
+            show_error(&app, "Could not revert file", e);
+            return;
+        }
+    };
+
+    let windows: State<'_, Mutex<Windows>> = app.state();
+    let mut windows = windows.lock().await;
+    let Some(document_state) = windows.get_document_state(&window_label) else {
+        return;
+    };
+    let contents = DocumentContents {
+        text: document.text,
+        path: document
+            .file
+            .as_ref()
+            .map(|file| file.path.display().to_string()),
+    };
+    document_state.file = document.file;
+    document_state.dirty = false;
+    update_window_title(&app, &window_label, document_state);
+    if let Err(e) = app.emit_to(window_label.as_str(), "document_opened", contents) {
+        log::error!("Could not send reverted document to window: {:?}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn open_document(app: AppHandle, webview_window: tauri::WebviewWindow) -> Result<(), ()> {
+    open_file_from_dialog(app, webview_window.label().to_string()).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn revert_document(
+    app: AppHandle,
+    webview_window: tauri::WebviewWindow,
+) -> Result<(), ()> {
+    revert_document_in_window(app, webview_window.label().to_string()).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn take_pending_document(
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+) -> Result<Option<DocumentContents>, String> {
+    let state = &mut state.lock().await;
+    let document_state = state
+        .get_document_state(webview_window.label())
+        .ok_or("Window has no document state")?;
+
+    Ok(document_state
+        .pending_text
+        .take()
+        .map(|text| DocumentContents {
+            text,
+            path: document_state
+                .file
+                .as_ref()
+                .map(|file| file.path.display().to_string()),
+        }))
+}
+
+#[tauri::command]
+pub async fn set_document_dirty(
+    app: AppHandle,
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+    dirty: bool,
+    revision: u64,
+) -> Result<(), String> {
+    let state = &mut state.lock().await;
+    let document_state = state
+        .get_document_state(webview_window.label())
+        .ok_or("Window has no document state")?;
+    document_state.revision = document_state.revision.max(revision);
+    if document_state.dirty != dirty {
+        document_state.dirty = dirty;
+        update_window_title(&app, webview_window.label(), document_state);
+    }
+    Ok(())
+}
+
+/// Writes the editor contents to the file of the window, asking for a location first when the
+/// document is untitled or `save_as` is set. Returns false when the user cancelled the dialog.
+///
+/// `revision` is the editor revision of `text`. The document stays dirty when the window reported
+/// a newer revision while the save was in progress.
+#[tauri::command]
+pub async fn save_document(
+    app: AppHandle,
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+    text: String,
+    save_as: bool,
+    revision: u64,
+) -> Result<bool, String> {
+    let window_label = webview_window.label();
+    let current_file = {
+        let state = &mut state.lock().await;
+        state
+            .get_document_state(window_label)
+            .ok_or("Window has no document state")?
+            .file
+            .clone()
+    };
+
+    let file = match current_file {
+        Some(file) if !save_as => file,
+        current_file => {
+            let current_path = current_file.as_ref().map(|file| file.path.as_path());
+            let Some(path) = pick_file_to_save(&app, window_label, current_path).await else {
+                return Ok(false);
+            };
+            DocumentFile {
+                path,
+                format: current_file.map(|file| file.format).unwrap_or_default(),
+            }
+        }
+    };
+
+    if let Err(e) = write_text_file(&file.path, &text, file.format) {
+        let message = format!("Could not save {}: {}", file.path.display(), e);
+        show_error(&app, "Could not save file", message.clone());
+        return Err(message);
+    }
+
+    let state = &mut state.lock().await;
+    let document_state = state
+        .get_document_state(window_label)
+        .ok_or("Window has no document state")?;
+    document_state.file = Some(file);
+    if document_state.revision <= revision {
+        document_state.dirty = false;
+    }
+    update_window_title(&app, window_label, document_state);
+    Ok(true)
+}
@@ -1,28 +1,88 @@
+#[cfg(feature = "bench")]
+mod bench;
+mod cli;
+mod document;
 mod scripts;
 mod settings;
 mod window;
 
-use crate::scripts::commands::{get_script_commands, reply_editor_request, run_script_command};
+use crate::cli::{
+    load_launch_documents, parse_cli_subcommand, parse_launch_targets, read_stdin,
+    run_cli_subcommand, LaunchTarget,
+};
+use crate::document::{
+    open_document, revert_document, save_document, set_document_dirty, take_pending_document,
+};
+use crate::scripts::commands::{
+    accept_script_preview, cancel_script_command, get_script_commands, get_script_logs,
+    reexecute_last_script, reply_editor_request, run_script_command,
+};
 use crate::scripts::loader::scripts::ScriptManager;
+use crate::scripts::loader::transpile_cache::{init_transpile_cache, TRANSPILE_CACHE_DIRECTORY};
+use crate::scripts::locations::{
+    add_script_location, bundled_script_directory, ensure_script_directory, load_script_locations,
+    open_user_script_directory, remove_script_location, user_script_directory,
+};
+use crate::scripts::watcher::ScriptWatcher;
 use crate::settings::{
-    get_settings, open_settings_window, set_preferred_language, set_theme, set_wrap_lines, Settings,
+    get_settings, open_settings_window, set_preferred_language, set_script_timeout, set_theme,
+    set_wrap_lines, Settings,
 };
-use crate::window::{menu, Windows};
+use crate::window::{menu, open_window, Windows};
+use std::path::Path;
 use tauri::async_runtime::{spawn, Mutex};
-use tauri::path::BaseDirectory;
 use tauri::{Listener, Manager, State, WindowEvent};
 use tauri_plugin_store::StoreExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let context = tauri::generate_context!();
+
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(subcommand) = parse_cli_subcommand(&args) {
+        #[cfg(windows)]
+        cli::attach_parent_console();
+        let exit_code = match subcommand {
+            Ok(subcommand) => {
+                match tauri::utils::platform::resource_dir(
+                    context.package_info(),
+                    &tauri::Env::default(),
+                ) {
+                    Ok(resource_dir) => run_cli_subcommand(
+                        subcommand,
+                        resource_dir.join("resources/scripts/"),
+                        &context.config().identifier,
+                    ),
+                    Err(e) => {
+                        eprintln!("Could not locate the bundled scripts: {:?}", e);
+                        2
+                    }
+                }
+            }
+            Err(usage) => {
+                eprintln!("{}", usage);
+                2
+            }
+        };
+        std::process::exit(exit_code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_os::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _, _| {
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
             let app = app.clone();
+            let targets = parse_launch_targets(&argv, Path::new(&cwd));
+            if targets.contains(&LaunchTarget::Stdin) {
+                // Only the arguments are forwarded from the second instance, its stdin is not
+                log::warn!("Cannot read stdin of another instance, opening an empty window");
+            }
             spawn(async move {
-                let windows: State<'_, Mutex<Windows>> = app.state();
-                let mut windows = windows.lock().await;
-                windows.create_window(&app).unwrap();
+                for document in load_launch_documents(targets, None) {
+                    if let Err(e) = open_window(&app, document).await {
+                        log::error!("Could not open window for another instance: {:?}", e);
+                    }
+                }
             });
         }))
         .plugin(
@@ -42,26 +102,52 @@ pub fn run() {
                 None => Settings::default(),
             };
 
-            let script_directory = app
-                .path()
-                .resolve("resources/scripts/", BaseDirectory::Resource)?;
+            init_transpile_cache(app.path().app_cache_dir()?.join(TRANSPILE_CACHE_DIRECTORY));
+
+            let bundled_script_directory = bundled_script_directory(app.handle())?;
+            let user_script_directory = user_script_directory(app.handle())?;
+            let user_script_directory = match ensure_script_directory(&user_script_directory) {
+                Ok(_) => Some(user_script_directory),
+                Err(e) => {
+                    log::warn!(
+                        "Could not create user script directory {}: {:?}",
+                        user_script_directory.display(),
+                        e
+                    );
+                    None
+                }
+            };
             let mut script_manager = ScriptManager::new();
-            let script_load_result = tauri::async_runtime::block_on(async {
-                script_manager.add_location(script_directory).await
-            });
+            tauri::async_runtime::block_on(load_script_locations(
+                &mut script_manager,
+                bundled_script_directory,
+                settings.script_locations(),
+                user_script_directory,
+            ));
 
-            if script_load_result.is_err() {
-                println!(
-                    "Could not load scripts: {:?}",
-                    script_load_result.unwrap_err()
-                )
-            }
+            let args: Vec<String> = std::env::args().collect();
+            let cwd = std::env::current_dir()?;
+            let targets = parse_launch_targets(&args, &cwd);
+            let stdin = if targets.contains(&LaunchTarget::Stdin) {
+                read_stdin()
+            } else {
+                None
+            };
 
+            // Nothing else can lock the windows before they are managed
             let mut windows = Windows::new();
-            windows.create_window(app.handle())?;
+            for document in load_launch_documents(targets, stdin) {
+                windows.create_window_with_document(app.handle(), document)?;
+            }
             app.manage(Mutex::new(settings));
             app.manage(Mutex::new(windows));
+
+            let mut script_watcher = ScriptWatcher::new(app.handle());
+            for location in script_manager.location_paths() {
+                script_watcher.watch(location);
+            }
             app.manage(Mutex::new(script_manager));
+            app.manage(Mutex::new(script_watcher));
 
             let app_handle = app.handle().clone();
             app.listen_any("open_settings", move |_| {
@@ -71,9 +157,9 @@ pub fn run() {
             app.listen_any("new_window", move |_| {
                 let app_handle = app_handle.clone();
                 spawn(async move {
-                    let windows: State<'_, Mutex<Windows>> = app_handle.state();
-                    let mut windows = windows.lock().await;
-                    windows.create_window(&app_handle).unwrap();
+                    if let Err(e) = open_window(&app_handle, None).await {
+                        log::error!("Could not open new window: {:?}", e);
+                    }
                 });
             });
 
@@ -85,6 +171,7 @@ pub fn run() {
                 let windows: State<'_, Mutex<Windows>> = window.app_handle().state();
                 let windows = &mut windows.blocking_lock();
                 windows.destroy_window(window.label());
+                menu::on_window_destroyed(window);
                 if !windows.has_open() {
                     window.app_handle().exit(0);
                 }
@@ -100,10 +187,23 @@ pub fn run() {
             set_theme,
             set_preferred_language,
             set_wrap_lines,
+            set_script_timeout,
             get_script_commands,
             run_script_command,
+            cancel_script_command,
+            get_script_logs,
+            reexecute_last_script,
             reply_editor_request,
+            accept_script_preview,
+            add_script_location,
+            remove_script_location,
+            open_user_script_directory,
+            open_document,
+            save_document,
+            revert_document,
+            set_document_dirty,
+            take_pending_document,
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
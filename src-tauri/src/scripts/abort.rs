@@ -0,0 +1,134 @@
+use deno_core::v8;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::{spawn, JoinHandle};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptAbortReason {
+    TimedOut(Duration),
+    Cancelled,
+}
+
+#[derive(Default)]
+struct ScriptAbortState {
+    isolate_handle: Option<v8::IsolateHandle>,
+    reason: Option<ScriptAbortReason>,
+    /// When the watchdog stops the run, moved back while the timeout is paused
+    deadline: Option<Instant>,
+    paused_since: Option<Instant>,
+    pauses: usize,
+}
+
+/// Shared between the window that started a script run and the runner thread executing it, so
+/// either side (or a watchdog) can stop the run. Terminating the isolate stops busy JavaScript,
+/// while the notification stops runs that are waiting on an op that will never resolve.
+#[derive(Clone, Default)]
+pub struct ScriptAbortHandle {
+    state: Arc<Mutex<ScriptAbortState>>,
+    notify: Arc<Notify>,
+    resumed: Arc<Notify>,
+}
+
+impl ScriptAbortHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the isolate that is running the script. If the run was already aborted before
+    /// the isolate existed, execution is terminated right away.
+    pub fn attach_isolate(&self, isolate_handle: v8::IsolateHandle) {
+        let mut state = self.state.lock().unwrap();
+        if state.reason.is_some() {
+            isolate_handle.terminate_execution();
+        }
+        state.isolate_handle = Some(isolate_handle);
+    }
+
+    /// Aborts the run. Returns false when the run was already aborted, in which case the original
+    /// reason is kept.
+    pub fn abort(&self, reason: ScriptAbortReason) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.reason.is_some() {
+                return false;
+            }
+            state.reason = Some(reason);
+            if let Some(isolate_handle) = &state.isolate_handle {
+                isolate_handle.terminate_execution();
+            }
+        }
+        self.notify.notify_waiters();
+        true
+    }
+
+    pub fn reason(&self) -> Option<ScriptAbortReason> {
+        self.state.lock().unwrap().reason
+    }
+
+    /// Resolves once the run has been aborted.
+    pub async fn aborted(&self) {
+        let notified = self.notify.notified();
+        if self.reason().is_some() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Starts a watchdog on the shared async runtime that aborts the run once `timeout` has
+    /// passed. The watchdog has to live outside of the runner thread, since a script stuck in a
+    /// loop blocks that thread entirely. Abort the returned handle once the run has finished.
+    pub fn start_watchdog(&self, timeout: Duration) -> JoinHandle<()> {
+        self.state.lock().unwrap().deadline = Some(Instant::now() + timeout);
+        let abort_handle = self.clone();
+        spawn(async move {
+            loop {
+                let deadline = abort_handle.state.lock().unwrap().deadline;
+                let Some(deadline) = deadline else {
+                    return;
+                };
+                tokio::time::sleep_until(deadline).await;
+
+                let (paused, deadline) = {
+                    let state = abort_handle.state.lock().unwrap();
+                    (state.paused_since.is_some(), state.deadline)
+                };
+                if paused {
+                    abort_handle.resumed.notified().await;
+                } else if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                    abort_handle.abort(ScriptAbortReason::TimedOut(timeout));
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Stops the timeout while the script waits for the user, who may take longer to answer than
+    /// a script is allowed to run. Every call has to be followed by `resume_timeout`.
+    pub fn pause_timeout(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.pauses == 0 {
+            state.paused_since = Some(Instant::now());
+        }
+        state.pauses += 1;
+    }
+
+    /// Continues the timeout, moving the deadline back by the time it was paused.
+    pub fn resume_timeout(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pauses = state.pauses.saturating_sub(1);
+            if state.pauses > 0 {
+                return;
+            }
+            if let Some(paused_since) = state.paused_since.take() {
+                state.deadline = state
+                    .deadline
+                    .map(|deadline| deadline + paused_since.elapsed());
+            }
+        }
+        // Stores a permit when the watchdog is not waiting yet, so it cannot miss the wakeup
+        self.resumed.notify_one();
+    }
+}
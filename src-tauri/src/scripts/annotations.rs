@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl DiagnosticSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticSeverity::Info => "info",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Error => "error",
+        }
+    }
+}
+
+/// A problem a script found in the document, which the editor underlines
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) severity: DiagnosticSeverity,
+    pub(crate) message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Decoration {
+    /// Highlights a range of the document, with an optional tooltip
+    #[serde(rename_all = "camelCase")]
+    Highlight {
+        start: usize,
+        end: usize,
+        title: Option<String>,
+    },
+    /// Shows a short text, such as a single symbol, in the gutter next to the line that holds
+    /// the position
+    #[serde(rename_all = "camelCase")]
+    GutterMarker {
+        position: usize,
+        text: String,
+        title: Option<String>,
+    },
+}
+
+/// The diagnostics and decorations a script publishes for the document. They replace the ones
+/// published before, and stay until the document is edited. Positions are offsets in UTF-16 code
+/// units.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentAnnotations {
+    #[serde(default)]
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    #[serde(default)]
+    pub(crate) decorations: Vec<Decoration>,
+}
+
+impl DocumentAnnotations {
+    /// Puts the start of every range before its end, so the editor does not have to
+    pub fn normalize(&mut self) {
+        for diagnostic in &mut self.diagnostics {
+            if diagnostic.start > diagnostic.end {
+                std::mem::swap(&mut diagnostic.start, &mut diagnostic.end);
+            }
+        }
+        for decoration in &mut self.decorations {
+            if let Decoration::Highlight { start, end, .. } = decoration {
+                if start > end {
+                    std::mem::swap(start, end);
+                }
+            }
+        }
+    }
+}
@@ -1,27 +1,52 @@
-use crate::scripts::loader::js_runtime::{transpile_extension, SnipModuleLoader};
+use crate::scripts::abort::{ScriptAbortHandle, ScriptAbortReason};
+use crate::scripts::annotations::DocumentAnnotations;
+use crate::scripts::crypto::{
+    op_snip_crypto_digest, op_snip_crypto_hmac, op_snip_crypto_random_bytes, op_snip_decode,
+    op_snip_encode,
+};
+use crate::scripts::diff::{diff_documents, DocumentDiff};
+use crate::scripts::edit_transaction::EditTransaction;
+use crate::scripts::error::ScriptError;
+use crate::scripts::loader::parameters::{
+    resolve_parameter_values, CommandParameter, ParameterValues,
+};
 use crate::scripts::loader::scripts::{
-    Command, EditorSelectionReplacement, EditorSelectionState, EditorState, Library, ScriptManager,
+    Command, EditorDocumentInfo, EditorSelectionReplacement, EditorSelectionState, EditorState,
+    Libraries, ScriptManager,
 };
-use crate::window::{WindowTask, Windows};
-use deno_core::error::AnyError;
-use deno_core::{
-    extension, op2, v8, ByteString, JsRuntime, OpState, Resource, ResourceId, RuntimeOptions,
+use crate::scripts::log::{ScriptLogEntry, ScriptLogLevel, ScriptLogs, ScriptRunLog};
+use crate::scripts::memory_editor::utf16_len;
+use crate::scripts::permissions::{ensure_permissions_granted, FileAccess, FileAccessKind};
+use crate::scripts::runtime::{ScriptRuntime, ScriptRuntimePool};
+use crate::scripts::web::{
+    op_snip_text_decode, op_snip_text_encode_into, op_snip_timer_create, op_snip_timer_sleep,
+    op_snip_url_parse, op_snip_url_parse_search_params, op_snip_url_set,
+    op_snip_url_stringify_search_params,
 };
+use crate::settings::Settings;
+use crate::window::{menu, WindowTask, Windows};
+use deno_core::error::{AnyError, JsError};
+use deno_core::{extension, op2, v8, ByteString, JsRuntime, OpState, Resource, ResourceId};
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::Duration;
 use tauri::ipc::Channel;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::mpsc::channel;
 use tokio::sync::oneshot::Sender;
 use tokio::sync::{mpsc, oneshot, Mutex};
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SearchResultCommandInfo {
     id: String,
+    content_hash: String,
     title: String,
     description: String,
+    parameters: Vec<CommandParameter>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -47,8 +72,10 @@ pub async fn get_script_commands(
             matched_indices: matching_command.matched_indices.clone(),
             command: SearchResultCommandInfo {
                 id: matching_command.command.id.clone(),
+                content_hash: matching_command.command.content_hash.clone(),
                 title: matching_command.command.info.title.clone(),
                 description: matching_command.command.info.description.clone(),
+                parameters: matching_command.command.info.parameters.clone(),
             },
         })
         .collect();
@@ -63,6 +90,28 @@ pub struct Script<'a> {
     location: &'a str,
 }
 
+/// How a status message of a script is shown. Unlike an error, an error status does not fail the
+/// run.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptStatusLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ScriptStatusLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptStatusLevel::Info => "info",
+            ScriptStatusLevel::Success => "success",
+            ScriptStatusLevel::Warning => "warning",
+            ScriptStatusLevel::Error => "error",
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase", tag = "event", content = "data")]
 pub enum ScriptRunEditorRequestEvent {
@@ -72,6 +121,14 @@ pub enum ScriptRunEditorRequestEvent {
     SetFullText(String),
     #[serde(rename_all = "camelCase")]
     SetError(String),
+    /// A message for the status bar, which stays until the next run or for `duration`
+    /// milliseconds
+    #[serde(rename_all = "camelCase")]
+    SetStatus {
+        level: ScriptStatusLevel,
+        message: String,
+        duration: Option<u64>,
+    },
     #[serde(rename_all = "camelCase")]
     GetPartialText {
         start: Option<usize>,
@@ -79,7 +136,46 @@ pub enum ScriptRunEditorRequestEvent {
         selection_index: Option<usize>,
     },
     #[serde(rename_all = "camelCase")]
+    GetDocumentLength,
+    #[serde(rename_all = "camelCase")]
+    AppendText(String),
+    #[serde(rename_all = "camelCase")]
     ReplaceSelections(Vec<EditorSelectionReplacement>),
+    #[serde(rename_all = "camelCase")]
+    SetSelections(EditorSelectionState),
+    #[serde(rename_all = "camelCase")]
+    InsertAt { position: usize, text: String },
+    #[serde(rename_all = "camelCase")]
+    ReplaceRange {
+        start: usize,
+        end: usize,
+        text: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    GetCursor,
+    /// Switches the highlighting of the document to the language with the given key
+    #[serde(rename_all = "camelCase")]
+    SetLanguage(String),
+    /// Underlines problems and decorates parts of the document until it is edited
+    #[serde(rename_all = "camelCase")]
+    SetAnnotations(DocumentAnnotations),
+    /// The edits of a completed script, which the editor applies as one transaction
+    #[serde(rename_all = "camelCase")]
+    ApplyEdits(Vec<ScriptRunEditorRequestEvent>),
+    #[serde(rename_all = "camelCase")]
+    Log(ScriptLogEntry),
+    #[serde(rename_all = "camelCase")]
+    Prompt {
+        message: String,
+        default_value: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Confirm { message: String },
+    #[serde(rename_all = "camelCase")]
+    PickFromList {
+        message: String,
+        options: Vec<String>,
+    },
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -103,6 +199,14 @@ pub enum InternalScriptRunEditorResponse {
     #[serde(rename_all = "camelCase")]
     GetFullText(String),
     GetPartialText(String),
+    /// The length of the document in UTF-16 code units
+    GetDocumentLength(usize),
+    /// The head of the main selection
+    GetCursor(usize),
+    Prompt(String),
+    Confirm(bool),
+    /// The index of the picked option
+    PickFromList(usize),
 }
 
 #[derive(Debug)]
@@ -112,25 +216,74 @@ pub enum InternalScriptRunEditorRequest {
         event: ScriptRunEditorRequestEvent,
         reply_sender: Sender<Result<InternalScriptRunEditorResponse, String>>,
     },
-    Error(String),
+    Error(ScriptError),
+}
+
+/// The changes a script would make, for the user to accept or reject
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptPreview {
+    diff: DocumentDiff,
+    /// Applied as a single transaction when the user accepts the changes
+    edits: Vec<ScriptRunEditorRequestEvent>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptRunOutcome {
+    Completed,
+    Cancelled,
+    /// The run completed in preview mode, without touching the document
+    Preview(ScriptPreview),
 }
 
 #[derive(Default)]
 pub struct WindowScriptState {
     reply_senders: HashMap<u64, Sender<Result<InternalScriptRunEditorResponse, String>>>,
     last_given_id: u64,
+    running_script: Option<ScriptAbortHandle>,
+    last_command_id: Option<String>,
+    /// The parameter values the last completed command ran with
+    last_parameters: ParameterValues,
+    /// The command and parameter values of the last preview, which only count as the last
+    /// completed command once the preview is accepted
+    previewed_command: Option<(String, ParameterValues)>,
+    logs: ScriptLogs,
+}
+
+impl WindowScriptState {
+    /// Remembers the command to re-execute it later. Returns whether it is the first command that
+    /// completed in the window.
+    fn complete_command(&mut self, command_id: String, parameters: ParameterValues) -> bool {
+        let is_first_completed_command = self.last_command_id.is_none();
+        self.last_command_id = Some(command_id);
+        self.last_parameters = parameters;
+        is_first_completed_command
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunScriptCommandEvent {
+    command_id: String,
+    parameters: ParameterValues,
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_script_command(
     window_state: State<'_, Mutex<Windows>>,
     script_manager: State<'_, Mutex<ScriptManager>>,
+    settings: State<'_, Mutex<Settings>>,
     webview_window: tauri::WebviewWindow,
     command_id: String,
+    parameters: Option<ParameterValues>,
+    preview: Option<bool>,
     editor_request_channel: Channel<ScriptRunEditorRequest>,
-    editor_state: EditorState,
-) -> Result<(), String> {
+    mut editor_state: EditorState,
+) -> Result<ScriptRunOutcome, ScriptError> {
     let window_label = webview_window.label().to_string();
+    let timeout = settings.lock().await.script_timeout();
 
     let (sender, mut receiver) = channel::<InternalScriptRunEditorRequest>(1);
 
@@ -144,63 +297,340 @@ pub async fn run_script_command(
         )
     };
 
-    if command.is_none() {
-        return Err("Could not find command for given id".to_string());
+    let Some(command) = command else {
+        return Err("Could not find command for given id".into());
+    };
+    let command_id = command.id.clone();
+    let parameters =
+        resolve_parameter_values(&command.info.parameters, parameters.unwrap_or_default())?;
+
+    if !ensure_permissions_granted(webview_window.app_handle(), &settings, &command).await {
+        return Err("The command was not allowed to access the files it needs".into());
     }
 
+    // A preview needs all edits, even from commands that stream them
+    let preview = preview.unwrap_or(false);
+    let mut transaction = (preview || !command.info.stream_edits)
+        .then(|| EditTransaction::new(editor_state.selection.clone()));
+
+    let abort_handle = ScriptAbortHandle::new();
     {
-        let window_state = &window_state.lock().await;
+        let window_state = &mut window_state.lock().await;
+        if let Some(document) = window_state.get_document_state(&window_label) {
+            editor_state.document.file_path = document
+                .file_path()
+                .map(|path| path.to_string_lossy().to_string());
+            editor_state.document.line_ending = document.line_ending();
+        }
+        let script_state = window_state
+            .get_script_state(&window_label)
+            .expect("Window should have script state");
+        script_state.running_script = Some(abort_handle.clone());
+        script_state
+            .logs
+            .start_run(command_id.clone(), command.info.title.clone());
         window_state
             .queue_task(
                 &window_label,
-                WindowTask::Script(ScriptTask::RunCommand(
-                    command.unwrap(),
+                WindowTask::Script(ScriptTask::RunCommand(ScriptRun {
+                    command,
                     libraries,
-                    sender,
+                    parameters: parameters.clone(),
+                    editor_request_channel: sender,
                     editor_state,
-                )),
+                    abort_handle: abort_handle.clone(),
+                    timeout,
+                })),
             )
             .await
-            .map_err(|_| "Could not send run script task to executor".to_string())?;
+            .map_err(|_| ScriptError::new("Could not send run script task to executor"))?;
+    }
+
+    let result = forward_editor_requests(
+        &window_state,
+        &window_label,
+        &mut receiver,
+        &editor_request_channel,
+        transaction.as_mut(),
+    )
+    .await;
+
+    let cancelled = abort_handle.reason() == Some(ScriptAbortReason::Cancelled);
+    // The edits of a script that failed or was cancelled are dropped with the transaction
+    let result = match transaction {
+        Some(transaction) if result.is_ok() && !cancelled => {
+            if preview {
+                preview_edits(
+                    &window_state,
+                    &window_label,
+                    &editor_request_channel,
+                    transaction,
+                )
+                .await
+                .map(Some)
+            } else {
+                apply_edits(&editor_request_channel, transaction.into_edits()).map(|_| None)
+            }
+        }
+        _ => result.map(|_| None),
+    };
+    let is_first_completed_command = {
+        let window_state = &mut window_state.lock().await;
+        let mut is_first_completed_command = false;
+        if let Some(script_state) = window_state.get_script_state(&window_label) {
+            script_state.running_script = None;
+            // Requests that were still waiting for an answer when the run stopped
+            script_state.reply_senders.clear();
+            if result.is_ok() && !cancelled {
+                if preview {
+                    script_state.previewed_command = Some((command_id, parameters));
+                } else {
+                    is_first_completed_command =
+                        script_state.complete_command(command_id, parameters);
+                }
+            }
+        }
+        is_first_completed_command
+    };
+    // Updating the menu waits for the main thread, which may itself be waiting for the lock
+    if is_first_completed_command {
+        menu::on_script_completed(&webview_window);
     }
 
+    if cancelled {
+        return Ok(ScriptRunOutcome::Cancelled);
+    }
+    result.map(|preview| match preview {
+        Some(preview) => ScriptRunOutcome::Preview(preview),
+        None => ScriptRunOutcome::Completed,
+    })
+}
+
+fn apply_edits(
+    editor_request_channel: &Channel<ScriptRunEditorRequest>,
+    edits: Vec<ScriptRunEditorRequestEvent>,
+) -> Result<(), ScriptError> {
+    if edits.is_empty() {
+        return Ok(());
+    }
+    editor_request_channel
+        .send(ScriptRunEditorRequest {
+            id: None,
+            event: ScriptRunEditorRequestEvent::ApplyEdits(edits),
+        })
+        .map_err(|_| ScriptError::new("Could not send event to editor"))
+}
+
+/// Compares the document with how the edits of the transaction would leave it
+async fn preview_edits(
+    window_state: &State<'_, Mutex<Windows>>,
+    window_label: &str,
+    editor_request_channel: &Channel<ScriptRunEditorRequest>,
+    transaction: EditTransaction,
+) -> Result<ScriptPreview, ScriptError> {
+    let original =
+        get_full_text_from_editor(window_state, window_label, editor_request_channel).await?;
+    let edited = transaction.apply_to(original.clone());
+    Ok(ScriptPreview {
+        diff: diff_documents(&original, &edited),
+        edits: transaction.into_edits(),
+    })
+}
+
+/// Runs the last completed command of a window again, with the same parameters. The run is
+/// started by the frontend, so it takes the same path as any other run and uses the current
+/// selection in the editor.
+pub async fn reexecute_last_script_in_window(app: AppHandle, window_label: String) {
+    let windows: State<'_, Mutex<Windows>> = app.state();
+    let last_run = windows
+        .lock()
+        .await
+        .get_script_state(&window_label)
+        .and_then(|script_state| {
+            script_state
+                .last_command_id
+                .clone()
+                .map(|command_id| (command_id, script_state.last_parameters.clone()))
+        });
+    if let Some((command_id, parameters)) = last_run {
+        app.emit_to(
+            window_label.as_str(),
+            "run_script_command",
+            RunScriptCommandEvent {
+                command_id,
+                parameters,
+            },
+        )
+        .expect("Could not emit run_script_command event");
+    }
+}
+
+#[tauri::command]
+pub async fn reexecute_last_script(
+    app: AppHandle,
+    webview_window: tauri::WebviewWindow,
+) -> Result<(), ()> {
+    reexecute_last_script_in_window(app, webview_window.label().to_string()).await;
+    Ok(())
+}
+
+/// Gives the request an id to answer it with, and keeps the sender for the answer until it comes
+async fn register_reply_sender(
+    window_state: &State<'_, Mutex<Windows>>,
+    window_label: &str,
+    event: ScriptRunEditorRequestEvent,
+    reply_sender: Sender<Result<InternalScriptRunEditorResponse, String>>,
+) -> ScriptRunEditorRequest {
+    let state = &mut window_state.lock().await;
+    let script_state = state
+        .get_script_state(window_label)
+        .expect("Window should have script state");
+    let id = script_state.last_given_id;
+    script_state.last_given_id = id + 1;
+    script_state.reply_senders.insert(id, reply_sender);
+    ScriptRunEditorRequest {
+        id: Some(id),
+        event,
+    }
+}
+
+async fn get_full_text_from_editor(
+    window_state: &State<'_, Mutex<Windows>>,
+    window_label: &str,
+    editor_request_channel: &Channel<ScriptRunEditorRequest>,
+) -> Result<String, ScriptError> {
+    let (sender, receiver) = oneshot::channel::<Result<InternalScriptRunEditorResponse, String>>();
+    let request = register_reply_sender(
+        window_state,
+        window_label,
+        ScriptRunEditorRequestEvent::GetFullText,
+        sender,
+    )
+    .await;
+    editor_request_channel
+        .send(request)
+        .map_err(|_| ScriptError::new("Could not send event to editor"))?;
+    match receiver.await {
+        Ok(Ok(InternalScriptRunEditorResponse::GetFullText(text))) => Ok(text),
+        _ => Err(ScriptError::new(
+            "Could not read the document from the editor",
+        )),
+    }
+}
+
+async fn forward_editor_requests(
+    window_state: &State<'_, Mutex<Windows>>,
+    window_label: &str,
+    receiver: &mut mpsc::Receiver<InternalScriptRunEditorRequest>,
+    editor_request_channel: &Channel<ScriptRunEditorRequest>,
+    mut transaction: Option<&mut EditTransaction>,
+) -> Result<(), ScriptError> {
+    // A script that reports an error fails, even when it completes afterwards
+    let mut reported_error = None;
     while !receiver.is_closed() {
-        let request = receiver.recv().await;
+        let mut request = receiver.recv().await;
+        if let Some(transaction) = transaction.as_deref_mut() {
+            if let Some(unhandled) = request.take() {
+                if transaction.needs_document_for(&unhandled) {
+                    let text = get_full_text_from_editor(
+                        window_state,
+                        window_label,
+                        editor_request_channel,
+                    )
+                    .await?;
+                    transaction.load_document(text);
+                }
+                request = transaction.handle_request(unhandled);
+            }
+        }
         if let Some(request) = request {
             let event = match request {
                 InternalScriptRunEditorRequest::Request(event) => {
+                    match &event {
+                        ScriptRunEditorRequestEvent::Log(entry) => {
+                            let state = &mut window_state.lock().await;
+                            if let Some(script_state) = state.get_script_state(window_label) {
+                                script_state.logs.push(entry.clone());
+                            }
+                        }
+                        ScriptRunEditorRequestEvent::SetError(message) => {
+                            reported_error = Some(message.clone());
+                        }
+                        _ => {}
+                    }
                     ScriptRunEditorRequest { id: None, event }
                 }
                 InternalScriptRunEditorRequest::RequestWithResponse {
                     event,
                     reply_sender,
-                } => {
-                    let state = &mut window_state.lock().await;
-                    let script_state = state
-                        .get_script_state(&window_label)
-                        .expect("Window should have script state");
-                    let id = script_state.last_given_id;
-                    script_state.last_given_id = id + 1;
-                    script_state.reply_senders.insert(id, reply_sender);
-                    ScriptRunEditorRequest {
-                        id: Some(id),
-                        event,
-                    }
-                }
-                InternalScriptRunEditorRequest::Error(e) => {
-                    println!("Received error: {}", e);
-                    return Err(e);
-                }
+                } => register_reply_sender(window_state, window_label, event, reply_sender).await,
+                InternalScriptRunEditorRequest::Error(e) => return Err(e),
             };
             editor_request_channel
                 .send(event)
-                .map_err(|_| "Could not send event to editor".to_string())?;
+                .map_err(|_| ScriptError::new("Could not send event to editor"))?;
         }
     }
 
+    match reported_error {
+        Some(message) => Err(ScriptError::new(message)),
+        None => Ok(()),
+    }
+}
+
+/// Makes the command of the last preview the one to re-execute, once the editor applied its edits
+#[tauri::command]
+pub async fn accept_script_preview(
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    let is_first_completed_command = {
+        let state = &mut state.lock().await;
+        let script_state = state
+            .get_script_state(webview_window.label())
+            .expect("Window should have script state");
+        let Some((command_id, parameters)) = script_state.previewed_command.take() else {
+            return Err("No preview to accept in this window".to_string());
+        };
+        script_state.complete_command(command_id, parameters)
+    };
+    if is_first_completed_command {
+        menu::on_script_completed(&webview_window);
+    }
     Ok(())
 }
 
+/// Returns the console output of the last few script runs in the window, newest run last
+#[tauri::command]
+pub async fn get_script_logs(
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+) -> Result<Vec<ScriptRunLog>, ()> {
+    let state = &mut state.lock().await;
+    Ok(state
+        .get_script_state(webview_window.label())
+        .map(|script_state| script_state.logs.runs())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn cancel_script_command(
+    state: State<'_, Mutex<Windows>>,
+    webview_window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    let state = &mut state.lock().await;
+    let script_state = state
+        .get_script_state(webview_window.label())
+        .expect("Window should have script state");
+
+    if let Some(abort_handle) = &script_state.running_script {
+        abort_handle.abort(ScriptAbortReason::Cancelled);
+        Ok(())
+    } else {
+        Err("No script is running in this window".to_string())
+    }
+}
+
 #[tauri::command]
 pub async fn reply_editor_request(
     state: State<'_, Mutex<Windows>>,
@@ -229,56 +659,90 @@ pub async fn reply_editor_request(
     }
 }
 
+pub struct ScriptRun {
+    pub command: Command,
+    pub libraries: Libraries,
+    pub parameters: ParameterValues,
+    pub editor_request_channel: mpsc::Sender<InternalScriptRunEditorRequest>,
+    pub editor_state: EditorState,
+    pub abort_handle: ScriptAbortHandle,
+    pub timeout: Duration,
+}
+
 pub enum ScriptTask {
-    RunCommand(
-        Command,
-        HashMap<String, Library>,
-        mpsc::Sender<InternalScriptRunEditorRequest>,
-        EditorState,
-    ),
+    RunCommand(ScriptRun),
 }
 
 struct EditorHandle {
     editor_request_channel: mpsc::Sender<InternalScriptRunEditorRequest>,
     editor_state: EditorState,
+    parameters: ParameterValues,
 }
 
 impl Resource for EditorHandle {}
 
-#[op2(async)]
-#[string]
-async fn snip_op_get_full_text(
-    state: Rc<RefCell<OpState>>,
+fn editor_request_channel(
+    state: &Rc<RefCell<OpState>>,
     editor_handle: u32,
-) -> Result<String, AnyError> {
-    let request_channel = {
-        let editor_handle_result = state
-            .borrow()
-            .resource_table
-            .get::<EditorHandle>(editor_handle);
-        if let Ok(editor_handle) = &editor_handle_result {
-            let channel = editor_handle.editor_request_channel.clone();
-            Ok(channel)
-        } else {
-            Err(AnyError::msg("Invalid editor handle"))
-        }
-    }?;
+) -> Result<mpsc::Sender<InternalScriptRunEditorRequest>, AnyError> {
+    Ok(state
+        .borrow()
+        .resource_table
+        .get::<EditorHandle>(editor_handle)
+        .map_err(|_| AnyError::msg("Invalid editor handle"))?
+        .editor_request_channel
+        .clone())
+}
+
+/// Sends a request to the editor and waits for its answer
+async fn request_from_editor(
+    state: &Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    event: ScriptRunEditorRequestEvent,
+) -> Result<InternalScriptRunEditorResponse, AnyError> {
+    let request_channel = editor_request_channel(state, editor_handle)?;
 
     let (sender, receiver) = oneshot::channel::<Result<InternalScriptRunEditorResponse, String>>();
-    let request = request_channel
+    request_channel
         .send(InternalScriptRunEditorRequest::RequestWithResponse {
-            event: ScriptRunEditorRequestEvent::GetFullText,
+            event,
             reply_sender: sender,
         })
-        .await;
-    if let Err(err) = request {
-        return Err(AnyError::msg(err.to_string()));
-    }
+        .await
+        .map_err(|err| AnyError::msg(err.to_string()))?;
 
-    let response = receiver
+    receiver
         .await
         .map_err(|err| AnyError::msg(err.to_string()))?
-        .map_err(AnyError::msg)?;
+        .map_err(AnyError::msg)
+}
+
+/// Sends a request to the editor that does not need an answer
+async fn send_to_editor(
+    state: &Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    event: ScriptRunEditorRequestEvent,
+) -> Result<(), AnyError> {
+    let request_channel = editor_request_channel(state, editor_handle)?;
+
+    request_channel
+        .send(InternalScriptRunEditorRequest::Request(event))
+        .await
+        .map_err(|err| AnyError::msg(err.to_string()))
+}
+
+#[op2(async)]
+#[string]
+async fn snip_op_get_full_text(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<String, AnyError> {
+    let response = request_from_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::GetFullText,
+    )
+    .await?;
     if let InternalScriptRunEditorResponse::GetFullText(fulltext) = response {
         Ok(fulltext)
     } else {
@@ -292,29 +756,12 @@ async fn snip_op_set_full_text(
     editor_handle: u32,
     #[string] full_text: String,
 ) -> Result<(), AnyError> {
-    let request_channel = {
-        let editor_handle_result = state
-            .borrow()
-            .resource_table
-            .get::<EditorHandle>(editor_handle);
-        if let Ok(editor_handle) = &editor_handle_result {
-            let channel = editor_handle.editor_request_channel.clone();
-            Ok(channel)
-        } else {
-            Err(AnyError::msg("Invalid editor handle"))
-        }
-    }?;
-
-    let request = request_channel
-        .send(InternalScriptRunEditorRequest::Request(
-            ScriptRunEditorRequestEvent::SetFullText(full_text),
-        ))
-        .await;
-    if let Err(err) = request {
-        return Err(AnyError::msg(err.to_string()));
-    }
-
-    Ok(())
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetFullText(full_text),
+    )
+    .await
 }
 
 #[op2(async)]
@@ -323,29 +770,32 @@ async fn snip_op_set_error(
     editor_handle: u32,
     #[string] error: String,
 ) -> Result<(), AnyError> {
-    let request_channel = {
-        let editor_handle_result = state
-            .borrow()
-            .resource_table
-            .get::<EditorHandle>(editor_handle);
-        if let Ok(editor_handle) = &editor_handle_result {
-            let channel = editor_handle.editor_request_channel.clone();
-            Ok(channel)
-        } else {
-            Err(AnyError::msg("Invalid editor handle"))
-        }
-    }?;
-
-    let request = request_channel
-        .send(InternalScriptRunEditorRequest::Request(
-            ScriptRunEditorRequestEvent::SetError(error),
-        ))
-        .await;
-    if let Err(err) = request {
-        return Err(AnyError::msg(err.to_string()));
-    }
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetError(error),
+    )
+    .await
+}
 
-    Ok(())
+#[op2(async)]
+async fn snip_op_set_status(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[serde] level: ScriptStatusLevel,
+    #[string] message: String,
+    #[serde] duration: Option<u64>,
+) -> Result<(), AnyError> {
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetStatus {
+            level,
+            message,
+            duration,
+        },
+    )
+    .await
 }
 
 #[op2(async)]
@@ -356,38 +806,16 @@ async fn snip_op_get_partial_text(
     #[bigint] start: usize,
     #[bigint] end: usize,
 ) -> Result<String, AnyError> {
-    let request_channel = {
-        let editor_handle_result = state
-            .borrow()
-            .resource_table
-            .get::<EditorHandle>(editor_handle);
-        if let Ok(editor_handle) = &editor_handle_result {
-            let channel = editor_handle.editor_request_channel.clone();
-            Ok(channel)
-        } else {
-            Err(AnyError::msg("Invalid editor handle"))
-        }
-    }?;
-
-    let (sender, receiver) = oneshot::channel::<Result<InternalScriptRunEditorResponse, String>>();
-    let request = request_channel
-        .send(InternalScriptRunEditorRequest::RequestWithResponse {
-            event: ScriptRunEditorRequestEvent::GetPartialText {
-                start: Some(start),
-                end: Some(end),
-                selection_index: None,
-            },
-            reply_sender: sender,
-        })
-        .await;
-    if let Err(err) = request {
-        return Err(AnyError::msg(err.to_string()));
-    }
-
-    let response = receiver
-        .await
-        .map_err(|err| AnyError::msg(err.to_string()))?
-        .map_err(AnyError::msg)?;
+    let response = request_from_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::GetPartialText {
+            start: Some(start),
+            end: Some(end),
+            selection_index: None,
+        },
+    )
+    .await?;
     if let InternalScriptRunEditorResponse::GetPartialText(partial_text) = response {
         Ok(partial_text)
     } else {
@@ -412,35 +840,417 @@ async fn snip_op_get_selection_state(
     }
 }
 
+#[op2(async)]
+#[serde]
+async fn snip_op_get_document_info(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<EditorDocumentInfo, AnyError> {
+    let editor_handle_result = state
+        .borrow()
+        .resource_table
+        .get::<EditorHandle>(editor_handle);
+    if let Ok(editor_handle) = &editor_handle_result {
+        Ok(editor_handle.editor_state.document.clone())
+    } else {
+        Err(AnyError::msg("Invalid editor handle"))
+    }
+}
+
+#[op2(async)]
+#[serde]
+async fn snip_op_get_parameters(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<ParameterValues, AnyError> {
+    let editor_handle_result = state
+        .borrow()
+        .resource_table
+        .get::<EditorHandle>(editor_handle);
+    if let Ok(editor_handle) = &editor_handle_result {
+        Ok(editor_handle.parameters.clone())
+    } else {
+        Err(AnyError::msg("Invalid editor handle"))
+    }
+}
+
 #[op2(async)]
 async fn snip_op_replace_selections(
     state: Rc<RefCell<OpState>>,
     editor_handle: u32,
     #[serde] replacements: Vec<EditorSelectionReplacement>,
 ) -> Result<(), AnyError> {
-    let request_channel = {
-        let editor_handle_result = state
-            .borrow()
-            .resource_table
-            .get::<EditorHandle>(editor_handle);
-        if let Ok(editor_handle) = &editor_handle_result {
-            let channel = editor_handle.editor_request_channel.clone();
-            Ok(channel)
-        } else {
-            Err(AnyError::msg("Invalid editor handle"))
-        }
-    }?;
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::ReplaceSelections(replacements),
+    )
+    .await
+}
 
-    let request = request_channel
-        .send(InternalScriptRunEditorRequest::Request(
-            ScriptRunEditorRequestEvent::ReplaceSelections(replacements),
-        ))
-        .await;
-    if let Err(err) = request {
-        return Err(AnyError::msg(err.to_string()));
+async fn get_document_length(
+    state: &Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<usize, AnyError> {
+    let response = request_from_editor(
+        state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::GetDocumentLength,
+    )
+    .await?;
+    if let InternalScriptRunEditorResponse::GetDocumentLength(length) = response {
+        Ok(length)
+    } else {
+        Err(AnyError::msg("Received incorrect response"))
     }
+}
 
-    Ok(())
+#[op2(async)]
+#[number]
+async fn snip_op_get_document_length(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<usize, AnyError> {
+    get_document_length(&state, editor_handle).await
+}
+
+/// Chunks are at least this long, so that every chunk contains at least one whole character
+const MIN_DOCUMENT_CHUNK_LENGTH: usize = 1024;
+/// Keeps a single chunk from growing as large as the documents chunked reading is meant for
+const MAX_DOCUMENT_CHUNK_LENGTH: usize = 1024 * 1024;
+
+/// Pages through a range of the document, one chunk per request to the editor, so that a script
+/// never holds more than a chunk of a large document at once. Positions are in UTF-16 code units.
+struct DocumentReader {
+    editor_handle: u32,
+    position: Cell<usize>,
+    end: usize,
+    chunk_length: usize,
+}
+
+impl Resource for DocumentReader {}
+
+#[op2(async)]
+#[smi]
+async fn snip_op_open_document_reader(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[number] start: usize,
+    #[serde] end: Option<usize>,
+    #[number] chunk_length: usize,
+) -> Result<ResourceId, AnyError> {
+    let document_length = get_document_length(&state, editor_handle).await?;
+    let end = end.unwrap_or(document_length).min(document_length);
+    let reader = DocumentReader {
+        editor_handle,
+        position: Cell::new(start.min(end)),
+        end,
+        chunk_length: chunk_length.clamp(MIN_DOCUMENT_CHUNK_LENGTH, MAX_DOCUMENT_CHUNK_LENGTH),
+    };
+    Ok(state.borrow_mut().resource_table.add(reader))
+}
+
+/// Reads the next chunk of the range, or nothing once the whole range has been read. A chunk can
+/// be one code unit shorter than requested, since the editor does not split surrogate pairs.
+#[op2(async)]
+#[string]
+async fn snip_op_read_document_chunk(
+    state: Rc<RefCell<OpState>>,
+    #[smi] reader_id: ResourceId,
+) -> Result<Option<String>, AnyError> {
+    let reader = state
+        .borrow()
+        .resource_table
+        .get::<DocumentReader>(reader_id)
+        .map_err(|_| AnyError::msg("Invalid document reader"))?;
+    let start = reader.position.get();
+    if start >= reader.end {
+        return Ok(None);
+    }
+    let end = (start + reader.chunk_length).min(reader.end);
+
+    let response = request_from_editor(
+        &state,
+        reader.editor_handle,
+        ScriptRunEditorRequestEvent::GetPartialText {
+            start: Some(start),
+            end: Some(end),
+            selection_index: None,
+        },
+    )
+    .await?;
+    let InternalScriptRunEditorResponse::GetPartialText(chunk) = response else {
+        return Err(AnyError::msg("Received incorrect response"));
+    };
+    let chunk_length = utf16_len(&chunk);
+    // The document got shorter while it was being read
+    if chunk_length == 0 {
+        return Ok(None);
+    }
+    reader.position.set(start + chunk_length);
+    Ok(Some(chunk))
+}
+
+#[op2(async)]
+async fn snip_op_append_text(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[string] text: String,
+) -> Result<(), AnyError> {
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::AppendText(text),
+    )
+    .await
+}
+
+#[op2(async)]
+async fn snip_op_set_selections(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[serde] selection_state: EditorSelectionState,
+) -> Result<(), AnyError> {
+    if selection_state.selections.is_empty() {
+        return Err(AnyError::msg(
+            "setSelections: expects at least one selection",
+        ));
+    }
+    if selection_state.main_selection_index >= selection_state.selections.len() {
+        return Err(AnyError::msg(
+            "setSelections: the main selection index is out of range",
+        ));
+    }
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetSelections(selection_state),
+    )
+    .await
+}
+
+#[op2(async)]
+async fn snip_op_insert_at(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[number] position: usize,
+    #[string] text: String,
+) -> Result<(), AnyError> {
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::InsertAt { position, text },
+    )
+    .await
+}
+
+#[op2(async)]
+async fn snip_op_replace_range(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[number] start: usize,
+    #[number] end: usize,
+    #[string] text: String,
+) -> Result<(), AnyError> {
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::ReplaceRange {
+            start: start.min(end),
+            end: start.max(end),
+            text,
+        },
+    )
+    .await
+}
+
+#[op2(async)]
+#[number]
+async fn snip_op_get_cursor(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+) -> Result<usize, AnyError> {
+    let response = request_from_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::GetCursor,
+    )
+    .await?;
+    if let InternalScriptRunEditorResponse::GetCursor(position) = response {
+        Ok(position)
+    } else {
+        Err(AnyError::msg("Received incorrect response"))
+    }
+}
+
+#[op2(async)]
+async fn snip_op_set_language(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[string] language: String,
+) -> Result<(), AnyError> {
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetLanguage(language),
+    )
+    .await
+}
+
+#[op2(async)]
+async fn snip_op_set_annotations(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[serde] mut annotations: DocumentAnnotations,
+) -> Result<(), AnyError> {
+    annotations.normalize();
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::SetAnnotations(annotations),
+    )
+    .await
+}
+
+/// Asks the user something through the editor. The timeout of the run is paused until the user
+/// answers. When the user cancels instead, the run is aborted and this never resolves.
+async fn request_user_input(
+    state: &Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    event: ScriptRunEditorRequestEvent,
+) -> Result<InternalScriptRunEditorResponse, AnyError> {
+    let abort_handle = state.borrow().try_borrow::<ScriptAbortHandle>().cloned();
+
+    if let Some(abort_handle) = &abort_handle {
+        abort_handle.pause_timeout();
+    }
+    let response = request_from_editor(state, editor_handle, event).await;
+    if let Some(abort_handle) = &abort_handle {
+        abort_handle.resume_timeout();
+    }
+    response
+}
+
+#[op2(async)]
+#[string]
+async fn snip_op_prompt(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[string] message: String,
+    #[string] default_value: Option<String>,
+) -> Result<String, AnyError> {
+    let response = request_user_input(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::Prompt {
+            message,
+            default_value,
+        },
+    )
+    .await?;
+    if let InternalScriptRunEditorResponse::Prompt(answer) = response {
+        Ok(answer)
+    } else {
+        Err(AnyError::msg("Received incorrect response"))
+    }
+}
+
+#[op2(async)]
+async fn snip_op_confirm(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[string] message: String,
+) -> Result<bool, AnyError> {
+    let response = request_user_input(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::Confirm { message },
+    )
+    .await?;
+    if let InternalScriptRunEditorResponse::Confirm(confirmed) = response {
+        Ok(confirmed)
+    } else {
+        Err(AnyError::msg("Received incorrect response"))
+    }
+}
+
+#[op2(async)]
+async fn snip_op_pick_from_list(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[string] message: String,
+    #[serde] options: Vec<String>,
+) -> Result<u32, AnyError> {
+    if options.is_empty() {
+        return Err(AnyError::msg("pickFromList: expects at least one option"));
+    }
+    let option_count = options.len();
+    let response = request_user_input(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::PickFromList { message, options },
+    )
+    .await?;
+    match response {
+        InternalScriptRunEditorResponse::PickFromList(index) if index < option_count => {
+            Ok(index as u32)
+        }
+        _ => Err(AnyError::msg("Received incorrect response")),
+    }
+}
+
+#[op2(async)]
+async fn snip_op_log(
+    state: Rc<RefCell<OpState>>,
+    editor_handle: u32,
+    #[serde] level: ScriptLogLevel,
+    #[string] message: String,
+) -> Result<(), AnyError> {
+    // The entry is timestamped here, since the script does not wait for it to be delivered
+    send_to_editor(
+        &state,
+        editor_handle,
+        ScriptRunEditorRequestEvent::Log(ScriptLogEntry::new(level, message)),
+    )
+    .await
+}
+
+/// Checks a path given by the script against the file access of the running command
+fn check_file_access(
+    state: &Rc<RefCell<OpState>>,
+    path: &str,
+    kind: FileAccessKind,
+) -> Result<PathBuf, AnyError> {
+    state
+        .borrow()
+        .try_borrow::<FileAccess>()
+        .ok_or_else(|| AnyError::msg("File access is not available"))?
+        .check(path, kind)
+        .map_err(AnyError::msg)
+}
+
+#[op2(async)]
+#[string]
+async fn snip_op_read_text_file(
+    state: Rc<RefCell<OpState>>,
+    #[string] path: String,
+) -> Result<String, AnyError> {
+    let path = check_file_access(&state, &path, FileAccessKind::Read)?;
+    tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AnyError::msg(format!("Could not read {}: {}", path.display(), e)))
+}
+
+#[op2(async)]
+async fn snip_op_write_text_file(
+    state: Rc<RefCell<OpState>>,
+    #[string] path: String,
+    #[string] contents: String,
+) -> Result<(), AnyError> {
+    let path = check_file_access(&state, &path, FileAccessKind::Write)?;
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| AnyError::msg(format!("Could not write {}: {}", path.display(), e)))
 }
 
 #[op2]
@@ -465,11 +1275,43 @@ extension!(
         snip_op_get_full_text,
         snip_op_set_full_text,
         snip_op_set_error,
+        snip_op_set_status,
         snip_op_get_partial_text,
         snip_op_get_selection_state,
+        snip_op_get_document_info,
+        snip_op_get_parameters,
         snip_op_replace_selections,
+        snip_op_get_document_length,
+        snip_op_open_document_reader,
+        snip_op_read_document_chunk,
+        snip_op_append_text,
+        snip_op_set_selections,
+        snip_op_insert_at,
+        snip_op_replace_range,
+        snip_op_get_cursor,
+        snip_op_set_language,
+        snip_op_set_annotations,
+        snip_op_log,
+        snip_op_prompt,
+        snip_op_confirm,
+        snip_op_pick_from_list,
+        snip_op_read_text_file,
+        snip_op_write_text_file,
         op_base64_atob,
         op_base64_btoa,
+        op_snip_crypto_digest,
+        op_snip_crypto_hmac,
+        op_snip_crypto_random_bytes,
+        op_snip_encode,
+        op_snip_decode,
+        op_snip_text_encode_into,
+        op_snip_text_decode,
+        op_snip_url_parse,
+        op_snip_url_set,
+        op_snip_url_parse_search_params,
+        op_snip_url_stringify_search_params,
+        op_snip_timer_create,
+        op_snip_timer_sleep,
     ],
     esm_entry_point = "ext:snip/index.ts",
     esm = [dir "js_runtime/snip", "index.ts"]
@@ -498,61 +1340,89 @@ fn install_editor_handle_id(
     Ok(())
 }
 
-async fn load_and_run_module(js_runtime: &mut JsRuntime, command: Command) -> Result<(), String> {
+async fn load_and_run_module(
+    js_runtime: &mut JsRuntime,
+    command: Command,
+) -> Result<(), ScriptError> {
     let module = command
         .get_deno_module_specifier()
-        .map_err(|e| format!("Invalid module: {}", e))?;
+        .map_err(|e| ScriptError::new(format!("Invalid module: {}", e)))?;
     let module_id = js_runtime
         .load_main_es_module(&module)
         .await
-        .map_err(|e| format!("Could not load module: {}", e))?;
+        .map_err(|e| ScriptError::from_any_error("Could not load module", e))?;
 
     let result = js_runtime.mod_evaluate(module_id);
     js_runtime
         .run_event_loop(Default::default())
         .await
-        .map_err(|e| format!("Uncaught error: {}", e))?;
+        .map_err(|e| ScriptError::from_any_error("Uncaught error", e))?;
 
-    result.await.map_err(|e| format!("Uncaught error: {}", e))?;
+    result
+        .await
+        .map_err(|e| ScriptError::from_any_error("Uncaught error", e))?;
 
     let namespace = js_runtime
         .get_module_namespace(module_id)
-        .map_err(|e| format!("Uncaught error: {}", e))?;
+        .map_err(|e| ScriptError::from_any_error("Uncaught error", e))?;
     {
         let mut scope = js_runtime.handle_scope();
         let namespace = v8::Local::new(&mut scope, namespace);
         let default = v8::String::new(&mut scope, "default").unwrap();
         let default_export = namespace.get(&mut scope, default.into()).unwrap();
         if !default_export.is_function() {
-            return Err("Module should contain a function as default export".to_string());
+            return Err("Module should contain a function as default export".into());
         }
         let default_export = v8::Local::<v8::Function>::try_from(default_export).unwrap();
-        default_export
+        let mut scope = v8::TryCatch::new(&mut scope);
+        if default_export
             .call(&mut scope, namespace.into(), &[])
-            .unwrap();
+            .is_none()
+        {
+            // Without an exception, execution was terminated because the run was aborted
+            return Err(match scope.exception() {
+                Some(exception) => {
+                    ScriptError::from_js_error(&JsError::from_v8_exception(&mut scope, exception))
+                }
+                None => "Script was stopped".into(),
+            });
+        }
     }
 
     js_runtime
         .run_event_loop(Default::default())
         .await
-        .map_err(|e| format!("Uncaught error: {}", e))
+        .map_err(|e| ScriptError::from_any_error("Uncaught error", e))
 }
 
-pub async fn handle_script_run(
-    command: Command,
-    libraries: HashMap<String, Library>,
-    editor_request_channel: mpsc::Sender<InternalScriptRunEditorRequest>,
-    editor_state: EditorState,
-) {
-    let mut js_runtime = JsRuntime::new(RuntimeOptions {
-        module_loader: Some(Rc::new(SnipModuleLoader::new(
-            command.get_location(),
-            libraries,
-        ))),
-        extension_transpiler: Some(Rc::new(transpile_extension)),
-        extensions: vec![snip::init_ops_and_esm()],
-        ..Default::default()
-    });
+pub async fn handle_script_run(run: ScriptRun, runtime: ScriptRuntime) {
+    let ScriptRun {
+        command,
+        libraries,
+        parameters,
+        editor_request_channel,
+        editor_state,
+        abort_handle,
+        timeout,
+    } = run;
+
+    let ScriptRuntime {
+        mut js_runtime,
+        module_loader,
+    } = runtime;
+    module_loader.load_command(
+        command.get_location(),
+        libraries,
+        command.info.dependencies.clone(),
+    );
+    js_runtime.op_state().borrow_mut().put(FileAccess::new(
+        &command.info.permissions,
+        Path::new(command.get_location()),
+    ));
+    js_runtime.op_state().borrow_mut().put(abort_handle.clone());
+    abort_handle.attach_isolate(js_runtime.v8_isolate().thread_safe_handle());
+    let watchdog = abort_handle.start_watchdog(timeout);
+
     let editor_handle_resource_id =
         js_runtime
             .op_state()
@@ -561,30 +1431,42 @@ pub async fn handle_script_run(
             .add(EditorHandle {
                 editor_request_channel: editor_request_channel.clone(),
                 editor_state,
+                parameters,
             });
 
-    let result = install_editor_handle_id(&mut js_runtime, editor_handle_resource_id);
-    if result.is_err() {
-        editor_request_channel
-            .send(InternalScriptRunEditorRequest::Error(result.unwrap_err()))
-            .await
-            .expect("Could not send error back to tauri");
-        return;
-    }
-    let result = load_and_run_module(&mut js_runtime, command).await;
-    if result.is_err() {
+    let result = match install_editor_handle_id(&mut js_runtime, editor_handle_resource_id) {
+        Ok(_) => {
+            tokio::select! {
+                result = load_and_run_module(&mut js_runtime, command) => result,
+                _ = abort_handle.aborted() => Ok(()),
+            }
+        }
+        Err(e) => Err(ScriptError::new(e)),
+    };
+    watchdog.abort();
+
+    let result = match abort_handle.reason() {
+        // The window that cancelled the run already knows about it, so there is nothing to report
+        Some(ScriptAbortReason::Cancelled) => Ok(()),
+        Some(ScriptAbortReason::TimedOut(timeout)) => Err(ScriptError::new(format!(
+            "Script did not finish within {} seconds and was stopped",
+            timeout.as_secs()
+        ))),
+        None => result,
+    };
+    if let Err(e) = result {
         editor_request_channel
-            .send(InternalScriptRunEditorRequest::Error(result.unwrap_err()))
+            .send(InternalScriptRunEditorRequest::Error(e))
             .await
             .expect("Could not send error back to tauri");
     }
 }
 
-pub async fn handle_script_task(event: ScriptTask) {
+pub async fn handle_script_task(event: ScriptTask, runtime_pool: &mut ScriptRuntimePool) {
     match event {
-        ScriptTask::RunCommand(command, libraries, editor_request_channel, editor_state) => {
-            println!("Running command {}", command.id);
-            handle_script_run(command, libraries, editor_request_channel, editor_state).await
+        ScriptTask::RunCommand(run) => {
+            log::debug!("Running command {}", run.command.id);
+            handle_script_run(run, runtime_pool.take()).await
         }
     }
 }
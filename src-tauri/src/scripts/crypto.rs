@@ -0,0 +1,93 @@
+use data_encoding::{Encoding, BASE32, BASE64, BASE64URL_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use deno_core::error::AnyError;
+use deno_core::op2;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac};
+
+/// Maps the algorithm names of the Web Crypto API to the ring digest algorithms
+fn digest_algorithm(algorithm: &str) -> Result<&'static digest::Algorithm, AnyError> {
+    match algorithm.to_ascii_uppercase().as_str() {
+        "SHA-1" => Ok(&digest::SHA1_FOR_LEGACY_USE_ONLY),
+        "SHA-256" => Ok(&digest::SHA256),
+        "SHA-384" => Ok(&digest::SHA384),
+        "SHA-512" => Ok(&digest::SHA512),
+        _ => Err(AnyError::msg(format!(
+            "Unsupported digest algorithm: {}",
+            algorithm
+        ))),
+    }
+}
+
+fn hmac_algorithm(algorithm: &str) -> Result<hmac::Algorithm, AnyError> {
+    match algorithm.to_ascii_uppercase().as_str() {
+        "SHA-1" => Ok(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY),
+        "SHA-256" => Ok(hmac::HMAC_SHA256),
+        "SHA-384" => Ok(hmac::HMAC_SHA384),
+        "SHA-512" => Ok(hmac::HMAC_SHA512),
+        _ => Err(AnyError::msg(format!(
+            "Unsupported HMAC algorithm: {}",
+            algorithm
+        ))),
+    }
+}
+
+/// Decoding is lenient about the case of hex digits, since users paste hashes in either case
+fn encoding(name: &str, for_decoding: bool) -> Result<Encoding, AnyError> {
+    match name {
+        "hex" if for_decoding => Ok(HEXLOWER_PERMISSIVE),
+        "hex" => Ok(HEXLOWER),
+        "base32" => Ok(BASE32),
+        "base64" => Ok(BASE64),
+        "base64url" => Ok(BASE64URL_NOPAD),
+        _ => Err(AnyError::msg(format!("Unsupported encoding: {}", name))),
+    }
+}
+
+#[op2]
+#[buffer]
+pub fn op_snip_crypto_digest(
+    #[string] algorithm: &str,
+    #[buffer] data: &[u8],
+) -> Result<Vec<u8>, AnyError> {
+    Ok(digest::digest(digest_algorithm(algorithm)?, data)
+        .as_ref()
+        .to_vec())
+}
+
+#[op2]
+#[buffer]
+pub fn op_snip_crypto_hmac(
+    #[string] algorithm: &str,
+    #[buffer] key: &[u8],
+    #[buffer] data: &[u8],
+) -> Result<Vec<u8>, AnyError> {
+    let key = hmac::Key::new(hmac_algorithm(algorithm)?, key);
+    Ok(hmac::sign(&key, data).as_ref().to_vec())
+}
+
+#[op2(fast)]
+pub fn op_snip_crypto_random_bytes(#[buffer] output: &mut [u8]) -> Result<(), AnyError> {
+    SystemRandom::new()
+        .fill(output)
+        .map_err(|_| AnyError::msg("Could not generate random bytes"))
+}
+
+#[op2]
+#[string]
+pub fn op_snip_encode(
+    #[string] encoding_name: &str,
+    #[buffer] data: &[u8],
+) -> Result<String, AnyError> {
+    Ok(encoding(encoding_name, false)?.encode(data))
+}
+
+#[op2]
+#[buffer]
+pub fn op_snip_decode(
+    #[string] encoding_name: &str,
+    #[string] text: &str,
+) -> Result<Vec<u8>, AnyError> {
+    encoding(encoding_name, true)?
+        .decode(text.trim().as_bytes())
+        .map_err(|e| AnyError::msg(format!("Invalid {} input: {}", encoding_name, e)))
+}
@@ -0,0 +1,589 @@
+use serde::Serialize;
+
+/// Unchanged lines shown before and after every change
+const CONTEXT_LINES: usize = 3;
+/// How many lines may differ before the diff stops looking for the smallest set of changes and
+/// shows everything that is left as replaced. This bounds the time and memory a diff of two very
+/// different documents takes.
+const MAX_LINE_EDIT_DISTANCE: usize = 1000;
+const MAX_CHARACTER_EDIT_DISTANCE: usize = 200;
+/// Changed characters are only highlighted within lines up to this long
+const MAX_HIGHLIGHTED_LINE_LENGTH: usize = 1000;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Unchanged,
+    Deleted,
+    Inserted,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    kind: DiffLineKind,
+    text: String,
+    /// The parts of a changed line that differ from the line it replaces, as start and end
+    /// offsets in UTF-16 code units
+    highlights: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// The first line of the hunk in the original document, counting from 1
+    old_start: usize,
+    old_count: usize,
+    /// The first line of the hunk in the changed document, counting from 1
+    new_start: usize,
+    new_count: usize,
+    lines: Vec<DiffLine>,
+}
+
+/// The changes between two versions of a document, grouped into hunks with some unchanged lines
+/// around them, like a unified diff
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiff {
+    hunks: Vec<DiffHunk>,
+    deleted_lines: usize,
+    inserted_lines: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Finds the changes that turn `old` into `new`, with one entry for every element of either.
+/// Uses the algorithm by Myers, after stripping the common prefix and suffix.
+fn edit_script<T: PartialEq>(old: &[T], new: &[T], max_distance: usize) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut script = vec![Edit::Equal; prefix];
+    script.extend(shortest_edit_script(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+        max_distance,
+    ));
+    script.extend(std::iter::repeat_n(Edit::Equal, suffix));
+    script
+}
+
+fn shortest_edit_script<T: PartialEq>(old: &[T], new: &[T], max_distance: usize) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let limit = (old.len() + new.len()).min(max_distance) as isize;
+    let offset = limit + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // The furthest x reached on every diagonal k = x - y, after every number of edits
+    let mut furthest = vec![0isize; (2 * limit + 3) as usize];
+    let mut trace = Vec::new();
+    let mut distance = None;
+    'search: for d in 0..=limit {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+                furthest[index(k + 1)]
+            } else {
+                furthest[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                trace.push(furthest.clone());
+                distance = Some(d);
+                break 'search;
+            }
+        }
+        trace.push(furthest.clone());
+    }
+
+    let Some(distance) = distance else {
+        let mut script = vec![Edit::Delete; old.len()];
+        script.extend(std::iter::repeat_n(Edit::Insert, new.len()));
+        return script;
+    };
+
+    let mut script = Vec::with_capacity(old.len() + new.len());
+    let (mut x, mut y) = (n, m);
+    for d in (1..=distance).rev() {
+        let previous = &trace[(d - 1) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && previous[index(k - 1)] < previous[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = previous[index(previous_k)];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if x == previous_x {
+            script.push(Edit::Insert);
+            y -= 1;
+        } else {
+            script.push(Edit::Delete);
+            x -= 1;
+        }
+    }
+    while x > 0 && y > 0 {
+        script.push(Edit::Equal);
+        x -= 1;
+        y -= 1;
+    }
+    script.reverse();
+    script
+}
+
+/// Turns the characters marked with `kind` into ranges of UTF-16 code units
+fn highlight_ranges(characters: &[char], script: &[Edit], kind: Edit) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut offset = 0;
+    let mut characters = characters.iter();
+    for edit in script {
+        if *edit != Edit::Equal && *edit != kind {
+            continue;
+        }
+        let Some(character) = characters.next() else {
+            break;
+        };
+        let end = offset + character.len_utf16();
+        if *edit == kind {
+            match ranges.last_mut() {
+                Some(last) if last.1 == offset => last.1 = end,
+                _ => ranges.push((offset, end)),
+            }
+        }
+        offset = end;
+    }
+    ranges
+}
+
+/// Marks the characters that differ between lines that replace each other
+fn highlight_replaced_lines(deleted: &mut [DiffLine], inserted: &mut [DiffLine]) {
+    for (old, new) in deleted.iter_mut().zip(inserted.iter_mut()) {
+        let old_characters: Vec<char> = old.text.chars().collect();
+        let new_characters: Vec<char> = new.text.chars().collect();
+        if old_characters.len() > MAX_HIGHLIGHTED_LINE_LENGTH
+            || new_characters.len() > MAX_HIGHLIGHTED_LINE_LENGTH
+        {
+            continue;
+        }
+        let script = edit_script(
+            &old_characters,
+            &new_characters,
+            MAX_CHARACTER_EDIT_DISTANCE,
+        );
+        old.highlights = highlight_ranges(&old_characters, &script, Edit::Delete);
+        new.highlights = highlight_ranges(&new_characters, &script, Edit::Insert);
+    }
+}
+
+fn push_changed_lines(
+    lines: &mut Vec<DiffLine>,
+    deleted: &mut Vec<DiffLine>,
+    inserted: &mut Vec<DiffLine>,
+) {
+    highlight_replaced_lines(deleted, inserted);
+    lines.append(deleted);
+    lines.append(inserted);
+}
+
+fn build_hunk(
+    old_lines: &[&str],
+    new_lines: &[&str],
+    script: &[Edit],
+    old_start: usize,
+    new_start: usize,
+) -> DiffHunk {
+    let mut lines = Vec::with_capacity(script.len());
+    let (mut old_index, mut new_index) = (old_start, new_start);
+    // Deleted lines directly followed by inserted lines replace them, and get highlights
+    let mut deleted: Vec<DiffLine> = Vec::new();
+    let mut inserted: Vec<DiffLine> = Vec::new();
+    for edit in script {
+        match edit {
+            Edit::Equal => {
+                push_changed_lines(&mut lines, &mut deleted, &mut inserted);
+                lines.push(DiffLine {
+                    kind: DiffLineKind::Unchanged,
+                    text: old_lines[old_index].to_string(),
+                    highlights: Vec::new(),
+                });
+                old_index += 1;
+                new_index += 1;
+            }
+            Edit::Delete => {
+                if !inserted.is_empty() {
+                    push_changed_lines(&mut lines, &mut deleted, &mut inserted);
+                }
+                deleted.push(DiffLine {
+                    kind: DiffLineKind::Deleted,
+                    text: old_lines[old_index].to_string(),
+                    highlights: Vec::new(),
+                });
+                old_index += 1;
+            }
+            Edit::Insert => {
+                inserted.push(DiffLine {
+                    kind: DiffLineKind::Inserted,
+                    text: new_lines[new_index].to_string(),
+                    highlights: Vec::new(),
+                });
+                new_index += 1;
+            }
+        }
+    }
+    push_changed_lines(&mut lines, &mut deleted, &mut inserted);
+
+    DiffHunk {
+        old_start: old_start + 1,
+        old_count: old_index - old_start,
+        new_start: new_start + 1,
+        new_count: new_index - new_start,
+        lines,
+    }
+}
+
+/// Compares two versions of a document line by line, and highlights the changed characters
+/// within lines that were replaced
+pub fn diff_documents(old: &str, new: &str) -> DocumentDiff {
+    let old_lines: Vec<&str> = old.split('\n').collect();
+    let new_lines: Vec<&str> = new.split('\n').collect();
+    let script = edit_script(&old_lines, &new_lines, MAX_LINE_EDIT_DISTANCE);
+
+    // The line in either document at every position of the script
+    let mut positions = Vec::with_capacity(script.len() + 1);
+    let (mut old_index, mut new_index) = (0, 0);
+    for edit in &script {
+        positions.push((old_index, new_index));
+        match edit {
+            Edit::Equal => {
+                old_index += 1;
+                new_index += 1;
+            }
+            Edit::Delete => old_index += 1,
+            Edit::Insert => new_index += 1,
+        }
+    }
+    positions.push((old_index, new_index));
+
+    let changes: Vec<usize> = script
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| **edit != Edit::Equal)
+        .map(|(position, _)| position)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut next_change = 0;
+    while next_change < changes.len() {
+        let start = changes[next_change].saturating_sub(CONTEXT_LINES);
+        let mut last_change = changes[next_change];
+        next_change += 1;
+        // Changes close enough to share their context lines end up in the same hunk
+        while next_change < changes.len()
+            && changes[next_change] <= last_change + 2 * CONTEXT_LINES + 1
+        {
+            last_change = changes[next_change];
+            next_change += 1;
+        }
+        let end = (last_change + CONTEXT_LINES + 1).min(script.len());
+        let (old_start, new_start) = positions[start];
+        hunks.push(build_hunk(
+            &old_lines,
+            &new_lines,
+            &script[start..end],
+            old_start,
+            new_start,
+        ));
+    }
+
+    DocumentDiff {
+        hunks,
+        deleted_lines: script.iter().filter(|edit| **edit == Edit::Delete).count(),
+        inserted_lines: script.iter().filter(|edit| **edit == Edit::Insert).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shows the lines of a hunk like a unified diff does
+    fn hunk_lines(hunk: &DiffHunk) -> Vec<String> {
+        hunk.lines
+            .iter()
+            .map(|line| {
+                let prefix = match line.kind {
+                    DiffLineKind::Unchanged => ' ',
+                    DiffLineKind::Deleted => '-',
+                    DiffLineKind::Inserted => '+',
+                };
+                format!("{}{}", prefix, line.text)
+            })
+            .collect()
+    }
+
+    fn hunk_ranges(diff: &DocumentDiff) -> Vec<(usize, usize, usize, usize)> {
+        diff.hunks
+            .iter()
+            .map(|hunk| {
+                (
+                    hunk.old_start,
+                    hunk.old_count,
+                    hunk.new_start,
+                    hunk.new_count,
+                )
+            })
+            .collect()
+    }
+
+    fn numbered_lines(count: usize) -> Vec<String> {
+        (1..=count)
+            .map(|number| format!("line {}", number))
+            .collect()
+    }
+
+    #[test]
+    fn identical_documents_have_no_hunks() {
+        let diff = diff_documents("a\nb\nc", "a\nb\nc");
+
+        assert!(diff.hunks.is_empty());
+        assert_eq!((diff.deleted_lines, diff.inserted_lines), (0, 0));
+    }
+
+    #[test]
+    fn empty_documents_have_no_hunks() {
+        let diff = diff_documents("", "");
+
+        assert!(diff.hunks.is_empty());
+    }
+
+    #[test]
+    fn inserted_lines() {
+        let diff = diff_documents("a\nb\nc", "a\nb\nx\ny\nc");
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 3, 1, 5)]);
+        assert_eq!(
+            hunk_lines(&diff.hunks[0]),
+            vec![" a", " b", "+x", "+y", " c"]
+        );
+        assert_eq!((diff.deleted_lines, diff.inserted_lines), (0, 2));
+    }
+
+    #[test]
+    fn deleted_lines() {
+        let diff = diff_documents("a\nb\nx\ny\nc", "a\nb\nc");
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 5, 1, 3)]);
+        assert_eq!(
+            hunk_lines(&diff.hunks[0]),
+            vec![" a", " b", "-x", "-y", " c"]
+        );
+        assert_eq!((diff.deleted_lines, diff.inserted_lines), (2, 0));
+    }
+
+    #[test]
+    fn replaced_line_in_the_middle_keeps_context_lines() {
+        let old = numbered_lines(11).join("\n");
+        let new = old.replace("line 6", "line six");
+
+        let diff = diff_documents(&old, &new);
+
+        assert_eq!(hunk_ranges(&diff), vec![(3, 7, 3, 7)]);
+        let hunk = &diff.hunks[0];
+        assert_eq!(
+            hunk_lines(hunk),
+            vec![
+                " line 3",
+                " line 4",
+                " line 5",
+                "-line 6",
+                "+line six",
+                " line 7",
+                " line 8",
+                " line 9",
+            ]
+        );
+        assert_eq!(hunk.lines[3].highlights, vec![(5, 6)]);
+        assert_eq!(hunk.lines[4].highlights, vec![(5, 8)]);
+        assert!(hunk.lines[0].highlights.is_empty());
+    }
+
+    #[test]
+    fn replaced_lines_are_paired_in_order() {
+        let diff = diff_documents("a\nfoo\nbar\nb", "a\nfoO\nbaz\nb");
+
+        let hunk = &diff.hunks[0];
+        assert_eq!(
+            hunk_lines(hunk),
+            vec![" a", "-foo", "-bar", "+foO", "+baz", " b"]
+        );
+        assert_eq!(hunk.lines[1].highlights, vec![(2, 3)]);
+        assert_eq!(hunk.lines[2].highlights, vec![(2, 3)]);
+        assert_eq!(hunk.lines[3].highlights, vec![(2, 3)]);
+        assert_eq!(hunk.lines[4].highlights, vec![(2, 3)]);
+    }
+
+    #[test]
+    fn empty_old_document() {
+        let diff = diff_documents("", "a\nb");
+
+        // An empty document still has a single empty line, which the first line replaces
+        assert_eq!(hunk_ranges(&diff), vec![(1, 1, 1, 2)]);
+        assert_eq!(hunk_lines(&diff.hunks[0]), vec!["-", "+a", "+b"]);
+        assert_eq!(diff.hunks[0].lines[1].highlights, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn empty_new_document() {
+        let diff = diff_documents("a\nb", "");
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 2, 1, 1)]);
+        assert_eq!(hunk_lines(&diff.hunks[0]), vec!["-a", "-b", "+"]);
+        assert_eq!(diff.hunks[0].lines[0].highlights, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn added_trailing_newline() {
+        let diff = diff_documents("a\nb", "a\nb\n");
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 2, 1, 3)]);
+        assert_eq!(hunk_lines(&diff.hunks[0]), vec![" a", " b", "+"]);
+        assert_eq!((diff.deleted_lines, diff.inserted_lines), (0, 1));
+    }
+
+    #[test]
+    fn removed_trailing_newline() {
+        let diff = diff_documents("a\nb\n", "a\nb");
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 3, 1, 2)]);
+        assert_eq!(hunk_lines(&diff.hunks[0]), vec![" a", " b", "-"]);
+        assert_eq!((diff.deleted_lines, diff.inserted_lines), (1, 0));
+    }
+
+    #[test]
+    fn highlights_count_utf16_code_units() {
+        let diff = diff_documents("héllo 😀 wörld", "héllo 😃 wörld");
+
+        let lines = &diff.hunks[0].lines;
+        assert_eq!(lines[0].highlights, vec![(6, 8)]);
+        assert_eq!(lines[1].highlights, vec![(6, 8)]);
+    }
+
+    #[test]
+    fn highlights_after_surrogate_pairs() {
+        let diff = diff_documents("😀😀 ab", "😀😀 aXYb");
+
+        let lines = &diff.hunks[0].lines;
+        assert!(lines[0].highlights.is_empty());
+        assert_eq!(lines[1].highlights, vec![(6, 8)]);
+    }
+
+    #[test]
+    fn long_lines_are_not_highlighted() {
+        let old = "a".repeat(MAX_HIGHLIGHTED_LINE_LENGTH + 1);
+        let new = format!("{}b", old);
+
+        let diff = diff_documents(&old, &new);
+
+        let lines = &diff.hunks[0].lines;
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.highlights.is_empty()));
+    }
+
+    #[test]
+    fn edit_script_within_limit_is_minimal() {
+        let old: Vec<char> = "abcd".chars().collect();
+        let new: Vec<char> = "acbd".chars().collect();
+
+        let script = edit_script(&old, &new, 10);
+
+        assert_eq!(
+            script.iter().filter(|edit| **edit != Edit::Equal).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn edit_script_over_limit_replaces_everything_between_prefix_and_suffix() {
+        let old: Vec<char> = "<abc>".chars().collect();
+        let new: Vec<char> = "<xyz>".chars().collect();
+
+        let script = edit_script(&old, &new, 2);
+
+        use Edit::{Delete, Equal, Insert};
+        assert_eq!(
+            script,
+            vec![Equal, Delete, Delete, Delete, Insert, Insert, Insert, Equal]
+        );
+    }
+
+    #[test]
+    fn documents_over_the_edit_limit_are_shown_as_replaced() {
+        let line_count = MAX_LINE_EDIT_DISTANCE / 2 + 1;
+        let old = numbered_lines(line_count).join("\n");
+        let new = numbered_lines(line_count)
+            .iter()
+            .map(|line| line.to_uppercase())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let diff = diff_documents(&old, &new);
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, line_count, 1, line_count)]);
+        let kinds: Vec<DiffLineKind> = diff.hunks[0].lines.iter().map(|line| line.kind).collect();
+        assert!(kinds[..line_count]
+            .iter()
+            .all(|kind| *kind == DiffLineKind::Deleted));
+        assert!(kinds[line_count..]
+            .iter()
+            .all(|kind| *kind == DiffLineKind::Inserted));
+        assert_eq!(
+            (diff.deleted_lines, diff.inserted_lines),
+            (line_count, line_count)
+        );
+    }
+
+    #[test]
+    fn changes_sharing_context_lines_are_merged() {
+        let old = numbered_lines(20);
+        let mut new = old.clone();
+        new[1] = "changed 2".to_string();
+        // Six unchanged lines in between, which both changes show as context
+        new[8] = "changed 9".to_string();
+
+        let diff = diff_documents(&old.join("\n"), &new.join("\n"));
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 12, 1, 12)]);
+    }
+
+    #[test]
+    fn distant_changes_get_their_own_hunks() {
+        let old = numbered_lines(20);
+        let mut new = old.clone();
+        new[1] = "changed 2".to_string();
+        // Seven unchanged lines in between, one more than the context of both changes
+        new[9] = "changed 10".to_string();
+
+        let diff = diff_documents(&old.join("\n"), &new.join("\n"));
+
+        assert_eq!(hunk_ranges(&diff), vec![(1, 5, 1, 5), (7, 7, 7, 7)]);
+    }
+}
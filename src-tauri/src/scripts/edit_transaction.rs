@@ -0,0 +1,110 @@
+use crate::scripts::commands::{InternalScriptRunEditorRequest, ScriptRunEditorRequestEvent};
+use crate::scripts::loader::scripts::EditorSelectionState;
+use crate::scripts::memory_editor::MemoryEditor;
+
+/// Holds back the edits a script makes, so that they reach the editor as a single transaction
+/// once the script completes, or not at all when it fails or is cancelled.
+pub struct EditTransaction {
+    /// The selection in the editor when the script started, which no edit has touched yet
+    selection: EditorSelectionState,
+    edits: Vec<ScriptRunEditorRequestEvent>,
+    /// The document with the pending edits applied. It is only loaded once the script reads the
+    /// document after editing it, since the editor does not know about the edits yet.
+    document: Option<MemoryEditor>,
+}
+
+fn is_edit(event: &ScriptRunEditorRequestEvent) -> bool {
+    matches!(
+        event,
+        ScriptRunEditorRequestEvent::SetFullText(_)
+            | ScriptRunEditorRequestEvent::ReplaceSelections(_)
+            | ScriptRunEditorRequestEvent::AppendText(_)
+            | ScriptRunEditorRequestEvent::SetSelections(_)
+            | ScriptRunEditorRequestEvent::InsertAt { .. }
+            | ScriptRunEditorRequestEvent::ReplaceRange { .. }
+            | ScriptRunEditorRequestEvent::SetLanguage(_)
+            // Annotations describe the edited document, so they are applied together with it
+            | ScriptRunEditorRequestEvent::SetAnnotations(_)
+    )
+}
+
+fn reads_document(event: &ScriptRunEditorRequestEvent) -> bool {
+    matches!(
+        event,
+        ScriptRunEditorRequestEvent::GetFullText
+            | ScriptRunEditorRequestEvent::GetPartialText { .. }
+            | ScriptRunEditorRequestEvent::GetDocumentLength
+            | ScriptRunEditorRequestEvent::GetCursor
+    )
+}
+
+impl EditTransaction {
+    pub fn new(selection: EditorSelectionState) -> Self {
+        Self {
+            selection,
+            edits: Vec::new(),
+            document: None,
+        }
+    }
+
+    /// Whether the document has to be loaded with [`EditTransaction::load_document`] before the
+    /// request can be handled
+    pub fn needs_document_for(&self, request: &InternalScriptRunEditorRequest) -> bool {
+        match request {
+            InternalScriptRunEditorRequest::RequestWithResponse { event, .. } => {
+                reads_document(event) && !self.edits.is_empty() && self.document.is_none()
+            }
+            _ => false,
+        }
+    }
+
+    fn edited_document(&self, text: String) -> MemoryEditor {
+        let mut document = MemoryEditor::with_selection(text, self.selection.clone());
+        for edit in &self.edits {
+            document.handle_request(InternalScriptRunEditorRequest::Request(edit.clone()));
+        }
+        document
+    }
+
+    /// Takes the document as it is in the editor and applies the pending edits to it
+    pub fn load_document(&mut self, text: String) {
+        self.document = Some(self.edited_document(text));
+    }
+
+    /// The document as it will be once the edits are applied to `text`, the document as it is in
+    /// the editor
+    pub fn apply_to(&self, text: String) -> String {
+        self.edited_document(text).into_text()
+    }
+
+    /// Keeps edits and answers reads of the edited document. Returns the requests that still
+    /// have to go to the editor.
+    pub fn handle_request(
+        &mut self,
+        request: InternalScriptRunEditorRequest,
+    ) -> Option<InternalScriptRunEditorRequest> {
+        match request {
+            InternalScriptRunEditorRequest::Request(event) if is_edit(&event) => {
+                if let Some(document) = &mut self.document {
+                    document.handle_request(InternalScriptRunEditorRequest::Request(event.clone()));
+                }
+                self.edits.push(event);
+                None
+            }
+            InternalScriptRunEditorRequest::RequestWithResponse { ref event, .. }
+                if reads_document(event) && !self.edits.is_empty() =>
+            {
+                match &mut self.document {
+                    Some(document) => document.handle_request(request),
+                    None => Some(request),
+                }
+            }
+            request => Some(request),
+        }
+    }
+
+    /// The edits to apply when the script completes, in the order the script made them
+    pub fn into_edits(self) -> Vec<ScriptRunEditorRequestEvent> {
+        self.edits
+    }
+}
@@ -0,0 +1,130 @@
+use deno_core::error::{AnyError, JsError, JsStackFrame};
+use deno_core::ModuleSpecifier;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStackFrame {
+    pub function_name: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<i64>,
+    pub column: Option<i64>,
+}
+
+impl ScriptStackFrame {
+    fn from_js_stack_frame(frame: &JsStackFrame) -> Self {
+        Self {
+            function_name: frame.function_name.clone(),
+            file: frame.file_name.as_deref().map(display_file_name),
+            line: frame.line_number,
+            column: frame.column_number,
+        }
+    }
+
+    /// Frames in the runtime itself, like the `Editor` API, are not interesting to script authors
+    fn is_script_frame(&self) -> bool {
+        self.file
+            .as_deref()
+            .is_some_and(|file| !file.starts_with("ext:"))
+    }
+}
+
+impl Display for ScriptStackFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.function_name.as_deref().unwrap_or("<anonymous>"))?;
+        if let Some(file) = &self.file {
+            write!(f, " ({}", file)?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, ":{}:{}", line, column)?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// An error that stopped a script. When the error was thrown by the script, the location points
+/// at the original TypeScript source, since stack frames are mapped back through source maps.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptError {
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<i64>,
+    pub column: Option<i64>,
+    pub stack: Vec<ScriptStackFrame>,
+}
+
+impl ScriptError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            file: None,
+            line: None,
+            column: None,
+            stack: vec![],
+        }
+    }
+
+    /// Converts an error returned by the runtime. JavaScript exceptions keep their stack, other
+    /// errors only keep their message, prefixed with `context`.
+    pub fn from_any_error(context: &str, error: AnyError) -> Self {
+        match error.downcast_ref::<JsError>() {
+            Some(js_error) => Self::from_js_error(js_error),
+            None => Self::new(format!("{}: {}", context, error)),
+        }
+    }
+
+    pub fn from_js_error(js_error: &JsError) -> Self {
+        let stack: Vec<ScriptStackFrame> = js_error
+            .frames
+            .iter()
+            .map(ScriptStackFrame::from_js_stack_frame)
+            .filter(ScriptStackFrame::is_script_frame)
+            .collect();
+        let (file, line, column) = stack
+            .first()
+            .map(|frame| (frame.file.clone(), frame.line, frame.column))
+            .unwrap_or_default();
+        Self {
+            message: js_error.exception_message.clone(),
+            file,
+            line,
+            column,
+            stack,
+        }
+    }
+}
+
+impl From<String> for ScriptError {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ScriptError {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)?;
+        for frame in &self.stack {
+            write!(f, "\n    at {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stack frames refer to modules by their URL, while users know their scripts by their path
+fn display_file_name(file_name: &str) -> String {
+    ModuleSpecifier::parse(file_name)
+        .ok()
+        .filter(|specifier| specifier.scheme() == "file")
+        .and_then(|specifier| specifier.to_file_path().ok())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| file_name.to_string())
+}
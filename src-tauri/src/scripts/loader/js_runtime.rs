@@ -1,19 +1,27 @@
-use crate::scripts::loader::scripts::Library;
+use crate::scripts::loader::scripts::{resolve_library, Libraries};
+use crate::scripts::loader::transpile::{emit_options, transpile_options};
+use crate::scripts::loader::transpile_cache::{transpile_cache, TranspiledModule};
+use crate::scripts::loader::version::VersionRequirement;
 use deno_ast::{MediaType, ParseParams};
 use deno_core::anyhow::Error;
-use deno_core::error::AnyError;
 use deno_core::{
-    resolve_import, ModuleCodeString, ModuleLoadResponse, ModuleLoader, ModuleName, ModuleSource,
-    ModuleSourceCode, ModuleSpecifier, ModuleType, RequestedModuleType, ResolutionKind,
-    SourceMapData,
+    resolve_import, ModuleLoadResponse, ModuleLoader, ModuleSource, ModuleSourceCode,
+    ModuleSpecifier, ModuleType, RequestedModuleType, ResolutionKind,
 };
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+/// Loads the modules of a command. A loader starts out without a command, so runtimes can be
+/// created before it is known which command they will run.
+#[derive(Default)]
 pub struct SnipModuleLoader {
     allowed_locations: Mutex<Vec<String>>,
-    libraries: HashMap<String, Library>,
+    libraries: Mutex<Libraries>,
+    dependencies: Mutex<HashMap<String, VersionRequirement>>,
+    /// Source maps of the transpiled modules, by module URL
+    source_maps: Mutex<HashMap<String, Vec<u8>>>,
 }
 
 impl ModuleLoader for SnipModuleLoader {
@@ -62,7 +70,7 @@ impl ModuleLoader for SnipModuleLoader {
                         }
                     }
 
-                    module = SnipModuleLoader::load_module_from_file(&module_specifier, &path)?;
+                    module = self.load_module_from_file(&module_specifier, &path)?;
                 }
                 "lib" => {
                     let path = module_specifier.path();
@@ -72,15 +80,16 @@ impl ModuleLoader for SnipModuleLoader {
                         path.split('/').next().unwrap().to_string()
                     };
                     let sub_path = path.strip_prefix(&library_name).unwrap().to_string();
-                    let library = self.libraries.get(&library_name).ok_or_else(|| {
-                        Error::msg(format!("Library not found: {}", library_name))
-                    })?;
-                    let location = library.get_location();
+                    let location = resolve_library(
+                        &self.libraries.lock().unwrap(),
+                        &library_name,
+                        self.dependencies.lock().unwrap().get(&library_name),
+                    )
+                    .map_err(Error::msg)?
+                    .get_location()
+                    .to_string();
                     let mut path = PathBuf::from(format!("{}{}", location, sub_path));
-                    self.allowed_locations
-                        .lock()
-                        .unwrap()
-                        .push(location.to_string());
+                    self.allowed_locations.lock().unwrap().push(location);
 
                     if path.is_dir() {
                         path = path.join("index");
@@ -95,7 +104,7 @@ impl ModuleLoader for SnipModuleLoader {
                         }
                     }
 
-                    module = SnipModuleLoader::load_module_from_file(&module_specifier, &path)?;
+                    module = self.load_module_from_file(&module_specifier, &path)?;
                 }
                 _ => {
                     return Err(Error::msg(format!(
@@ -109,16 +118,36 @@ impl ModuleLoader for SnipModuleLoader {
 
         ModuleLoadResponse::Sync(module_load())
     }
+
+    fn get_source_map(&self, file_name: &str) -> Option<Cow<'_, [u8]>> {
+        self.source_maps
+            .lock()
+            .unwrap()
+            .get(file_name)
+            .map(|source_map| Cow::Owned(source_map.clone()))
+    }
 }
 
 impl SnipModuleLoader {
-    pub fn new(script_path: impl Into<String>, libraries: HashMap<String, Library>) -> Self {
-        SnipModuleLoader {
-            allowed_locations: Mutex::new(vec![script_path.into()]),
-            libraries,
-        }
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets up the loader for the command at `script_path`. `lib:` imports resolve to the newest
+    /// installed version of a library that satisfies the requirement in `dependencies`.
+    pub fn load_command(
+        &self,
+        script_path: impl Into<String>,
+        libraries: Libraries,
+        dependencies: HashMap<String, VersionRequirement>,
+    ) {
+        *self.allowed_locations.lock().unwrap() = vec![script_path.into()];
+        *self.libraries.lock().unwrap() = libraries;
+        *self.dependencies.lock().unwrap() = dependencies;
     }
+
     fn load_module_from_file(
+        &self,
         original_module_specifier: &ModuleSpecifier,
         path: &Path,
     ) -> Result<ModuleSource, Error> {
@@ -139,22 +168,34 @@ impl SnipModuleLoader {
 
         let code = std::fs::read_to_string(path)?;
         let code = if should_transpile {
-            let parsed = deno_ast::parse_module(ParseParams {
-                specifier: module_specifier.clone(),
-                text: code.into(),
-                media_type,
-                capture_tokens: false,
-                scope_analysis: false,
-                maybe_syntax: None,
-            })?;
-            let transpiled_source = parsed
-                .transpile(
-                    &Default::default(),
-                    &Default::default(),
-                    &Default::default(),
-                )?
-                .into_source();
-            transpiled_source.text
+            let transpile = || -> Result<TranspiledModule, Error> {
+                let parsed = deno_ast::parse_module(ParseParams {
+                    specifier: module_specifier.clone(),
+                    text: code.as_str().into(),
+                    media_type,
+                    capture_tokens: false,
+                    scope_analysis: false,
+                    maybe_syntax: None,
+                })?;
+                let transpiled_source = parsed
+                    .transpile(&transpile_options(), &Default::default(), &emit_options())?
+                    .into_source();
+                Ok(TranspiledModule {
+                    code: transpiled_source.text,
+                    source_map: transpiled_source.source_map,
+                })
+            };
+            let transpiled_module = match transpile_cache() {
+                Some(cache) => cache.get_or_transpile(path, &code, transpile)?,
+                None => transpile()?,
+            };
+            if let Some(source_map) = transpiled_module.source_map {
+                self.source_maps
+                    .lock()
+                    .unwrap()
+                    .insert(module_specifier.to_string(), source_map.into_bytes());
+            }
+            transpiled_module.code
         } else {
             code
         };
@@ -169,27 +210,3 @@ impl SnipModuleLoader {
         ))
     }
 }
-
-pub fn transpile_extension(
-    name: ModuleName,
-    code: ModuleCodeString,
-) -> Result<(ModuleCodeString, Option<SourceMapData>), AnyError> {
-    // We expect all extensions to work with typescript, so we parse it as typescript
-    let parsed = deno_ast::parse_module(ParseParams {
-        specifier: ModuleSpecifier::parse(name.to_string().as_str())?,
-        text: code.into(),
-        media_type: MediaType::TypeScript,
-        capture_tokens: false,
-        scope_analysis: false,
-        maybe_syntax: None,
-    })?;
-    let transpiled_source = parsed
-        .transpile(
-            &Default::default(),
-            &Default::default(),
-            &Default::default(),
-        )?
-        .into_source();
-    let result_source = transpiled_source.text.into();
-    Ok((result_source, None))
-}
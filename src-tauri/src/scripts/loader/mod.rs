@@ -1,2 +1,6 @@
 pub mod js_runtime;
+pub mod parameters;
 pub mod scripts;
+pub mod transpile;
+pub mod transpile_cache;
+pub mod version;
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The values of the parameters of a command run, by parameter name
+pub type ParameterValues = HashMap<String, Value>;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ParameterKind {
+    #[serde(rename_all = "camelCase")]
+    String { default: Option<String> },
+    #[serde(rename_all = "camelCase")]
+    Number {
+        default: Option<f64>,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Boolean { default: Option<bool> },
+    #[serde(rename_all = "camelCase")]
+    Enum {
+        options: Vec<String>,
+        default: Option<String>,
+    },
+}
+
+/// An option of a command, which the user fills in before the command runs. Parameters without
+/// a default have to be given a value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandParameter {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub kind: ParameterKind,
+}
+
+impl CommandParameter {
+    fn default_value(&self) -> Option<Value> {
+        match &self.kind {
+            ParameterKind::String { default } => default.clone().map(Value::from),
+            ParameterKind::Number { default, .. } => default.map(Value::from),
+            ParameterKind::Boolean { default } => default.map(Value::from),
+            ParameterKind::Enum { default, .. } => default.clone().map(Value::from),
+        }
+    }
+
+    /// Checks that `value` has the type of the parameter and lies within its bounds
+    fn check_value(&self, value: &Value) -> Result<(), String> {
+        let valid = match (&self.kind, value) {
+            (ParameterKind::String { .. }, Value::String(_)) => true,
+            (ParameterKind::Number { min, max, .. }, Value::Number(number)) => {
+                let number = number.as_f64().unwrap_or(f64::NAN);
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(format!(
+                        "Parameter \"{}\" should be between {} and {}",
+                        self.name,
+                        min.map_or("-∞".to_string(), |min| min.to_string()),
+                        max.map_or("∞".to_string(), |max| max.to_string())
+                    ));
+                }
+                true
+            }
+            (ParameterKind::Boolean { .. }, Value::Bool(_)) => true,
+            (ParameterKind::Enum { options, .. }, Value::String(option)) => {
+                if !options.contains(option) {
+                    return Err(format!(
+                        "Parameter \"{}\" should be one of {}",
+                        self.name,
+                        options.join(", ")
+                    ));
+                }
+                true
+            }
+            _ => false,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "Parameter \"{}\" should be a {}",
+                self.name,
+                self.type_name()
+            ))
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.kind {
+            ParameterKind::String { .. } | ParameterKind::Enum { .. } => "string",
+            ParameterKind::Number { .. } => "number",
+            ParameterKind::Boolean { .. } => "boolean",
+        }
+    }
+
+    /// Converts a value given on the command line to the type of the parameter
+    pub fn parse_value(&self, value: &str) -> Result<Value, String> {
+        let parsed = match self.kind {
+            ParameterKind::String { .. } | ParameterKind::Enum { .. } => Some(Value::from(value)),
+            ParameterKind::Number { .. } => value.parse::<f64>().ok().map(Value::from),
+            ParameterKind::Boolean { .. } => value.parse::<bool>().ok().map(Value::from),
+        };
+        let parsed = parsed.ok_or_else(|| {
+            format!(
+                "Parameter \"{}\" should be a {}",
+                self.name,
+                self.type_name()
+            )
+        })?;
+        self.check_value(&parsed)?;
+        Ok(parsed)
+    }
+}
+
+/// Checks the parameters declared by a command, so mistakes show up when the command is loaded
+/// instead of when it runs.
+pub fn validate_parameters(parameters: &[CommandParameter]) -> Result<(), String> {
+    for (index, parameter) in parameters.iter().enumerate() {
+        if parameters[..index]
+            .iter()
+            .any(|other| other.name == parameter.name)
+        {
+            return Err(format!(
+                "Parameter \"{}\" is declared more than once",
+                parameter.name
+            ));
+        }
+        if let ParameterKind::Enum { options, .. } = &parameter.kind {
+            if options.is_empty() {
+                return Err(format!(
+                    "Parameter \"{}\" should have at least one option",
+                    parameter.name
+                ));
+            }
+        }
+        if let Some(default) = parameter.default_value() {
+            parameter.check_value(&default)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the values given for a run and fills in the defaults of the parameters that were not
+/// given.
+pub fn resolve_parameter_values(
+    parameters: &[CommandParameter],
+    mut values: ParameterValues,
+) -> Result<ParameterValues, String> {
+    if let Some(name) = values
+        .keys()
+        .find(|name| !parameters.iter().any(|parameter| &parameter.name == *name))
+    {
+        return Err(format!("The command has no parameter \"{}\"", name));
+    }
+
+    for parameter in parameters {
+        match values.get(&parameter.name) {
+            Some(value) => parameter.check_value(value)?,
+            None => {
+                let default = parameter
+                    .default_value()
+                    .ok_or_else(|| format!("Parameter \"{}\" needs a value", parameter.name))?;
+                values.insert(parameter.name.clone(), default);
+            }
+        }
+    }
+    Ok(values)
+}
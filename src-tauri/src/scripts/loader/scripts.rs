@@ -1,8 +1,13 @@
-use data_encoding::HEXUPPER;
+use crate::document::LineEnding;
+use crate::scripts::loader::parameters::{validate_parameters, CommandParameter};
+use crate::scripts::loader::version::{VersionRequirement, SCRIPT_API_VERSION};
+use crate::scripts::permissions::ScriptPermissions;
+use data_encoding::{HEXLOWER, HEXUPPER};
 use deno_core::{ModuleResolutionError, ModuleSpecifier};
-use ring::digest::{Context, SHA256};
+use ring::digest::{digest, Context, SHA256};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
@@ -19,11 +24,31 @@ pub struct CommandInfo {
     pub description: String,
     api: u32,
     entry_point: String,
+    /// Version requirements for the libraries used by the command, by library name
+    #[serde(default)]
+    pub dependencies: HashMap<String, VersionRequirement>,
+    /// Files the command needs to access, which the user is asked to allow before the first run
+    #[serde(default)]
+    pub permissions: ScriptPermissions,
+    /// Options the user fills in before the command runs, in the order they are shown
+    #[serde(default)]
+    pub parameters: Vec<CommandParameter>,
+    /// Applies edits to the document as soon as the command makes them, instead of as a single
+    /// transaction once it completes
+    #[serde(default)]
+    pub stream_edits: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct Command {
+    /// Identifies the command as `scope/name`, where the scope comes from the location of the
+    /// command. Unlike the content hash, it does not change when the command is updated.
     pub id: String,
+    /// SHA-256 of command.json, which changes whenever the command definition changes.
+    pub content_hash: String,
+    /// SHA-256 of every file in the command folder, which changes whenever the code of the
+    /// command changes. Consent to access files is tied to it.
+    pub files_hash: String,
     location: String,
     pub info: CommandInfo,
 }
@@ -52,34 +77,137 @@ impl Library {
     pub fn get_location(&self) -> &str {
         self.location.as_str()
     }
+
+    pub fn version(&self) -> u32 {
+        self.info.version
+    }
+}
+
+/// The installed libraries by name. Every name maps to all installed versions of the library,
+/// ordered from the newest to the oldest version.
+pub type Libraries = HashMap<String, Vec<Library>>;
+
+/// Picks the newest installed version of a library that satisfies the requirement, or the newest
+/// version when there is no requirement.
+pub fn resolve_library<'a>(
+    libraries: &'a Libraries,
+    name: &str,
+    requirement: Option<&VersionRequirement>,
+) -> Result<&'a Library, String> {
+    let versions = libraries
+        .get(name)
+        .ok_or_else(|| format!("Library not found: {}", name))?;
+    versions
+        .iter()
+        .filter(|library| match requirement {
+            Some(requirement) => requirement.matches(library.version()),
+            None => true,
+        })
+        .max_by_key(|library| library.version())
+        .ok_or_else(|| {
+            let installed = versions
+                .iter()
+                .map(|library| library.version().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "No version of library {} matches \"{}\", installed versions: {}",
+                name,
+                requirement.map(ToString::to_string).unwrap_or_default(),
+                installed
+            )
+        })
+}
+
+/// Rejects scripts that were written for a script API this version of Snip does not provide, and
+/// versions that dependencies could not refer to.
+fn check_script_versions(manifest_path: &Path, api: u32, version: u32) -> Result<(), Error> {
+    if api == 0 || api > SCRIPT_API_VERSION {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "{} targets script API {}, but this version of Snip supports API 1 to {}",
+                manifest_path.display(),
+                api,
+                SCRIPT_API_VERSION
+            ),
+        ));
+    }
+    if version == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} has version 0, versions start at 1",
+                manifest_path.display()
+            ),
+        ));
+    }
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorSelection {
-    anchor: usize,
-    head: usize,
-    text: Option<String>,
+    pub(crate) anchor: usize,
+    pub(crate) head: usize,
+    pub(crate) text: Option<String>,
+}
+
+impl EditorSelection {
+    pub fn cursor(position: usize) -> Self {
+        Self {
+            anchor: position,
+            head: position,
+            text: None,
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.anchor.min(self.head)
+    }
+
+    pub fn end(&self) -> usize {
+        self.anchor.max(self.head)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorSelectionState {
-    main_selection_index: usize,
-    selections: Vec<EditorSelection>,
+    pub(crate) main_selection_index: usize,
+    pub(crate) selections: Vec<EditorSelection>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorSelectionReplacement {
-    index: usize,
-    text: String,
+    pub(crate) index: usize,
+    pub(crate) text: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorState {
     pub(crate) selection: EditorSelectionState,
+    #[serde(default)]
+    pub(crate) document: EditorDocumentInfo,
+}
+
+/// What a command can find out about the document it runs on, as it was when the command started
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorDocumentInfo {
+    /// The language the editor highlights the document as, such as `json`
+    pub(crate) language: Option<String>,
+    /// The length of the document in UTF-16 code units
+    pub(crate) length: usize,
+    pub(crate) line_count: usize,
+    /// Where the document is saved. Only the backend knows this, so it is filled in there.
+    #[serde(default)]
+    pub(crate) file_path: Option<String>,
+    /// The line ending the document is saved with. The editor itself always uses LF.
+    #[serde(default)]
+    pub(crate) line_ending: LineEnding,
 }
 
 impl Command {
@@ -91,10 +219,29 @@ impl Command {
     }
 }
 
+/// Where a script location comes from. When several locations define a command or library with
+/// the same name, the one from the location with the highest priority wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptLocationKind {
+    Bundled,
+    Extra,
+    User,
+}
+
+struct ScriptLocation {
+    path: PathBuf,
+    kind: ScriptLocationKind,
+    scope: String,
+    commands: Vec<Command>,
+    libraries: Vec<Library>,
+}
+
 pub struct ScriptManager {
-    script_locations: Vec<PathBuf>,
+    script_locations: Vec<ScriptLocation>,
     commands: HashMap<String, Command>,
-    libraries: HashMap<String, Library>,
+    libraries: Libraries,
+    /// Warnings that were logged while indexing, so reloading the scripts does not repeat them
+    reported_warnings: HashSet<String>,
 }
 
 pub struct CommandSearchResult<'a> {
@@ -109,6 +256,7 @@ impl ScriptManager {
             script_locations: vec![],
             commands: HashMap::new(),
             libraries: HashMap::new(),
+            reported_warnings: HashSet::new(),
         }
     }
 
@@ -130,67 +278,241 @@ impl ScriptManager {
         commands
     }
 
+    /// Finds a command by its id. When the command has been overridden by a location with a
+    /// higher priority, the overriding command is returned instead.
     pub fn find_command_by_id(&self, id: &str) -> Option<&Command> {
-        self.commands.get(id)
+        self.commands.get(id).or_else(|| {
+            id.split_once('/')
+                .and_then(|(_, name)| self.find_command_by_name(name))
+        })
+    }
+
+    pub fn find_command_by_name(&self, name: &str) -> Option<&Command> {
+        self.commands
+            .values()
+            .find(|command| command.info.name == name)
+    }
+
+    /// Finds a command by its id, its name or, failing both, the best fuzzy match on its title.
+    pub fn resolve_command(&self, query: &str) -> Option<&Command> {
+        self.find_command_by_id(query)
+            .or_else(|| self.find_command_by_name(query))
+            .or_else(|| {
+                self.find_commands_by_title(query)
+                    .first()
+                    .map(|result| result.command)
+            })
     }
 
-    pub async fn add_location(&mut self, location: PathBuf) -> Result<(), Error> {
-        self.load_scripts(&location).await?;
-        self.script_locations.push(location);
+    pub async fn add_location(
+        &mut self,
+        location: PathBuf,
+        kind: ScriptLocationKind,
+    ) -> Result<(), Error> {
+        let scope = Self::location_scope(&location, kind);
+        let mut script_location = ScriptLocation {
+            path: location,
+            kind,
+            scope,
+            commands: vec![],
+            libraries: vec![],
+        };
+        Self::load_scripts(&mut script_location).await?;
+
+        // Keep the locations ordered by priority, so later locations override earlier ones
+        let index = self
+            .script_locations
+            .partition_point(|existing| existing.kind <= kind);
+        self.script_locations.insert(index, script_location);
+        self.rebuild_index();
         Ok(())
     }
 
+    /// Chooses the scope used in the ids of the commands in a location. Extra locations are scoped
+    /// by their folder name and a short hash of their canonical path, so the ids of their commands
+    /// stay the same when other locations are added, removed or reordered.
+    fn location_scope(location: &Path, kind: ScriptLocationKind) -> String {
+        match kind {
+            ScriptLocationKind::Bundled => "snip".to_string(),
+            ScriptLocationKind::User => "user".to_string(),
+            ScriptLocationKind::Extra => {
+                let name = location
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "extra".to_string());
+                let path =
+                    std::fs::canonicalize(location).unwrap_or_else(|_| location.to_path_buf());
+                let path_digest = digest(&SHA256, path.to_string_lossy().as_bytes());
+                format!("{}-{}", name, HEXLOWER.encode(&path_digest.as_ref()[..4]))
+            }
+        }
+    }
+
+    pub fn remove_location(&mut self, location: &Path) {
+        self.script_locations
+            .retain(|script_location| script_location.path != location);
+        self.rebuild_index();
+    }
+
+    pub fn location_paths(&self) -> impl Iterator<Item = &Path> {
+        self.script_locations
+            .iter()
+            .map(|script_location| script_location.path.as_path())
+    }
+
+    /// Reloads only the locations that contain one of the changed paths. Returns whether any
+    /// location was reloaded.
+    pub async fn reload_changed_locations(&mut self, changed_paths: &[PathBuf]) -> bool {
+        let mut reloaded = false;
+        for script_location in &mut self.script_locations {
+            if !changed_paths
+                .iter()
+                .any(|path| path.starts_with(&script_location.path))
+            {
+                continue;
+            }
+            log::info!("Reloading scripts from {}", script_location.path.display());
+            if let Err(e) = Self::load_scripts(script_location).await {
+                // The location itself is gone, so none of its scripts can be run anymore
+                log::warn!(
+                    "Could not reload scripts on location {}: {:?}",
+                    script_location.path.display(),
+                    e
+                );
+                script_location.commands.clear();
+                script_location.libraries.clear();
+            }
+            reloaded = true;
+        }
+        if reloaded {
+            self.rebuild_index();
+        }
+        reloaded
+    }
+
     pub async fn reload_scripts(&mut self) {
-        let locations = self.script_locations.clone();
-        for location in &locations {
-            println!("Reloading scripts from {}", location.display());
-            let result = &self.load_scripts(location).await;
+        for script_location in &mut self.script_locations {
+            log::info!("Reloading scripts from {}", script_location.path.display());
+            let result = Self::load_scripts(script_location).await;
             if let Err(e) = result {
-                println!(
+                log::warn!(
                     "Could not reload scripts on location {}: {:?}",
-                    location.display(),
+                    script_location.path.display(),
                     e
                 );
             }
         }
+        self.rebuild_index();
     }
 
-    async fn load_scripts(&mut self, location: &Path) -> Result<(), Error> {
-        let command_path = location.join("commands");
-        if command_path.is_dir() {
-            self.load_commands(&command_path).await?;
+    /// Recomputes the commands and libraries that are available to the user from all locations.
+    /// A command overrides any command with the same name that comes from a location with a lower
+    /// priority. Libraries are kept in every version, where a location with a higher priority only
+    /// overrides the same version of a library.
+    fn rebuild_index(&mut self) {
+        let mut warnings = vec![];
+        let mut commands_by_name: HashMap<&str, &Command> = HashMap::new();
+        let mut libraries_by_version: HashMap<(&str, u32), &Library> = HashMap::new();
+        for script_location in &self.script_locations {
+            for command in &script_location.commands {
+                let overridden = commands_by_name.insert(command.info.name.as_str(), command);
+                if let Some(overridden) = overridden {
+                    warnings.push(format!(
+                        "Command {} overrides {}, which has the same name",
+                        command.id, overridden.id
+                    ));
+                }
+            }
+            for library in &script_location.libraries {
+                libraries_by_version
+                    .insert((library.info.name.as_str(), library.version()), library);
+            }
+        }
+
+        self.commands = commands_by_name
+            .into_values()
+            .map(|command| (command.id.clone(), command.clone()))
+            .collect();
+        let mut libraries = Libraries::new();
+        for ((name, _), library) in libraries_by_version {
+            libraries
+                .entry(name.to_string())
+                .or_default()
+                .push(library.clone());
+        }
+        for versions in libraries.values_mut() {
+            versions.sort_by_key(|library| Reverse(library.version()));
+        }
+        self.libraries = libraries;
+
+        for command in self.commands.values() {
+            for (name, requirement) in &command.info.dependencies {
+                if let Err(e) = resolve_library(&self.libraries, name, Some(requirement)) {
+                    warnings.push(format!(
+                        "Dependency of command {} cannot be satisfied: {}",
+                        command.id, e
+                    ));
+                }
+            }
         }
-        let library_path = location.join("library");
-        if library_path.is_dir() {
-            self.load_libraries(&library_path).await?;
+
+        for warning in warnings {
+            if self.reported_warnings.insert(warning.clone()) {
+                log::warn!("{}", warning);
+            }
         }
+    }
+
+    async fn load_scripts(script_location: &mut ScriptLocation) -> Result<(), Error> {
+        let command_path = script_location.path.join("commands");
+        script_location.commands = if command_path.is_dir() {
+            Self::load_commands(&command_path, &script_location.scope).await?
+        } else {
+            vec![]
+        };
+        let library_path = script_location.path.join("library");
+        script_location.libraries = if library_path.is_dir() {
+            Self::load_libraries(&library_path).await?
+        } else {
+            vec![]
+        };
         Ok(())
     }
 
-    async fn load_commands(&mut self, location: &Path) -> Result<(), Error> {
-        let main_dir = location.read_dir()?;
-        for entry in main_dir {
-            let entry = entry?;
+    async fn load_commands(location: &Path, scope: &str) -> Result<Vec<Command>, Error> {
+        let mut commands: Vec<Command> = vec![];
+        let mut entries = location.read_dir()?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
             let meta = entry.metadata()?;
             if meta.is_file() {
                 continue;
             }
 
-            let command = Self::load_command(entry.path()).await;
+            let command = Self::load_command(entry.path(), scope).await;
             if let Ok(script) = command {
-                self.commands.insert(script.id.to_string(), script);
+                if let Some(existing) = commands.iter().find(|existing| existing.id == script.id) {
+                    log::warn!(
+                        "Ignoring command at {}, the command at {} has the same id {}",
+                        script.location,
+                        existing.location,
+                        script.id
+                    );
+                    continue;
+                }
+                commands.push(script);
             } else {
-                println!(
-                    "WARNING: Could not load script at location {}: {:?}",
+                log::warn!(
+                    "Could not load script at location {}: {:?}",
                     entry.path().display(),
                     command.unwrap_err()
                 )
             }
         }
-        Ok(())
+        Ok(commands)
     }
 
-    async fn load_command(folder: PathBuf) -> Result<Command, Error> {
+    async fn load_command(folder: PathBuf, scope: &str) -> Result<Command, Error> {
         let command_file_path = folder.join("command.json");
         let command_file_meta = metadata(&command_file_path).await?;
         if !command_file_meta.is_file() {
@@ -202,10 +524,17 @@ impl ScriptManager {
                 ),
             ));
         }
-        let id = sha256_digest_file(&command_file_path).await?;
+        let content_hash = sha256_digest_file(&command_file_path).await?;
 
-        let file = File::open(command_file_path)?;
+        let file = File::open(&command_file_path)?;
         let command_info: CommandInfo = serde_json::from_reader(file)?;
+        check_script_versions(&command_file_path, command_info.api, command_info.version)?;
+        validate_parameters(&command_info.parameters).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {}", command_file_path.display(), e),
+            )
+        })?;
 
         let entry_point_file_path = folder.join(command_info.entry_point.clone());
         let entry_point_file_meta = metadata(&entry_point_file_path).await?;
@@ -219,17 +548,22 @@ impl ScriptManager {
             ));
         }
 
+        let files_hash = sha256_digest_folder(&folder).await?;
+
         Ok(Command {
-            id,
+            id: format!("{}/{}", scope, command_info.name),
+            content_hash,
+            files_hash,
             location: String::from(folder.to_str().unwrap()),
             info: command_info,
         })
     }
 
-    async fn load_libraries(&mut self, location: &Path) -> Result<(), Error> {
-        let main_dir = location.read_dir()?;
-        for entry in main_dir {
-            let entry = entry?;
+    async fn load_libraries(location: &Path) -> Result<Vec<Library>, Error> {
+        let mut libraries = vec![];
+        let mut entries = location.read_dir()?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
             let meta = entry.metadata()?;
             if meta.is_file() {
                 continue;
@@ -247,25 +581,26 @@ impl ScriptManager {
                     if sub_meta.is_file() {
                         continue;
                     }
-                    self.load_library_or_warn(&sub_entry.path()).await;
+                    libraries.extend(Self::load_library_or_warn(&sub_entry.path()).await);
                 }
             } else {
-                self.load_library_or_warn(&entry.path()).await;
+                libraries.extend(Self::load_library_or_warn(&entry.path()).await);
             }
         }
-        Ok(())
+        Ok(libraries)
     }
 
-    async fn load_library_or_warn(&mut self, folder: &Path) {
+    async fn load_library_or_warn(folder: &Path) -> Option<Library> {
         let library = Self::load_library(folder).await;
         if let Ok(library) = library {
-            self.libraries.insert(library.info.name.clone(), library);
+            Some(library)
         } else {
-            println!(
-                "WARNING: Could not load library at location {}: {:?}",
+            log::warn!(
+                "Could not load library at location {}: {:?}",
                 folder.display(),
                 library.unwrap_err()
-            )
+            );
+            None
         }
     }
 
@@ -282,8 +617,9 @@ impl ScriptManager {
             ));
         }
 
-        let file = File::open(library_file_path)?;
+        let file = File::open(&library_file_path)?;
         let library_info: LibraryInfo = serde_json::from_reader(file)?;
+        check_script_versions(&library_file_path, library_info.api, library_info.version)?;
 
         Ok(Library {
             location: String::from(folder.to_str().unwrap()),
@@ -291,7 +627,7 @@ impl ScriptManager {
         })
     }
 
-    pub fn get_libraries_snapshot(&self) -> HashMap<String, Library> {
+    pub fn get_libraries_snapshot(&self) -> Libraries {
         self.libraries.clone()
     }
 }
@@ -312,3 +648,110 @@ async fn sha256_digest_file(path: &PathBuf) -> Result<String, Error> {
 
     Ok(HEXUPPER.encode(digest.as_ref()))
 }
+
+/// Hashes the relative path and the contents of every file in the folder and its subfolders, in a
+/// fixed order, so that renaming, adding or changing any file changes the hash.
+async fn sha256_digest_folder(folder: &Path) -> Result<String, Error> {
+    let mut files = vec![];
+    let mut folders = vec![folder.to_path_buf()];
+    while let Some(current) = folders.pop() {
+        let mut entries = tokio::fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                folders.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+
+    let mut context = Context::new(&SHA256);
+    for file in files {
+        let relative_path = file.strip_prefix(folder).unwrap_or(&file);
+        let contents = tokio::fs::read(&file).await?;
+        context.update(relative_path.to_string_lossy().as_bytes());
+        context.update(&[0]);
+        context.update(&(contents.len() as u64).to_le_bytes());
+        context.update(&contents);
+    }
+    let digest = context.finish();
+
+    Ok(HEXUPPER.encode(digest.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(name: &str, version: u32) -> Library {
+        Library {
+            location: format!("/scripts/library/{}_{}", name, version),
+            info: LibraryInfo {
+                name: name.to_string(),
+                version,
+                api: SCRIPT_API_VERSION,
+            },
+        }
+    }
+
+    fn libraries(name: &str, versions: &[u32]) -> Libraries {
+        let versions = versions
+            .iter()
+            .map(|version| library(name, *version))
+            .collect();
+        HashMap::from([(name.to_string(), versions)])
+    }
+
+    fn requirement(source: &str) -> VersionRequirement {
+        VersionRequirement::try_from(source.to_string()).unwrap()
+    }
+
+    #[test]
+    fn resolve_library_picks_newest_matching_version() {
+        let libraries = libraries("text", &[2, 5, 1, 3, 4]);
+
+        let resolved = resolve_library(&libraries, "text", Some(&requirement(">=2, <4")));
+
+        assert_eq!(resolved.unwrap().version(), 3);
+    }
+
+    #[test]
+    fn resolve_library_picks_newest_version_without_requirement() {
+        let libraries = libraries("text", &[1, 3, 2]);
+
+        let resolved = resolve_library(&libraries, "text", None);
+
+        assert_eq!(resolved.unwrap().version(), 3);
+    }
+
+    #[test]
+    fn resolve_library_picks_exact_version() {
+        let libraries = libraries("text", &[1, 2, 3]);
+
+        let resolved = resolve_library(&libraries, "text", Some(&requirement("2")));
+
+        assert_eq!(resolved.unwrap().version(), 2);
+    }
+
+    #[test]
+    fn resolve_library_reports_installed_versions_when_none_match() {
+        let libraries = libraries("text", &[3, 1]);
+
+        let error = resolve_library(&libraries, "text", Some(&requirement(">=4"))).unwrap_err();
+
+        assert_eq!(
+            error,
+            "No version of library text matches \">=4\", installed versions: 3, 1"
+        );
+    }
+
+    #[test]
+    fn resolve_library_reports_missing_library() {
+        let libraries = libraries("text", &[1]);
+
+        let error = resolve_library(&libraries, "json", None).unwrap_err();
+
+        assert_eq!(error, "Library not found: json");
+    }
+}
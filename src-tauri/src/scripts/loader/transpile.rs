@@ -0,0 +1,42 @@
+use deno_ast::{EmitOptions, MediaType, ParseParams, SourceMapOption, TranspileOptions};
+use deno_core::error::AnyError;
+use deno_core::{ModuleCodeString, ModuleName, ModuleSpecifier, SourceMapData};
+
+pub fn transpile_options() -> TranspileOptions {
+    Default::default()
+}
+
+/// Source maps are kept separate from the emitted code, so the runtime can map stack traces of
+/// errors back to the original TypeScript.
+pub fn emit_options() -> EmitOptions {
+    EmitOptions {
+        source_map: SourceMapOption::Separate,
+        ..Default::default()
+    }
+}
+
+/// Transpiles the TypeScript of the `snip` extension. The build script includes this file to
+/// transpile the extension the same way when it creates the startup snapshot.
+#[cfg_attr(not(feature = "bench"), allow(dead_code))]
+pub fn transpile_extension(
+    name: ModuleName,
+    code: ModuleCodeString,
+) -> Result<(ModuleCodeString, Option<SourceMapData>), AnyError> {
+    // We expect all extensions to work with typescript, so we parse it as typescript
+    let parsed = deno_ast::parse_module(ParseParams {
+        specifier: ModuleSpecifier::parse(name.to_string().as_str())?,
+        text: code.into(),
+        media_type: MediaType::TypeScript,
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+    })?;
+    let transpiled_source = parsed
+        .transpile(&transpile_options(), &Default::default(), &emit_options())?
+        .into_source();
+    let result_source = transpiled_source.text.into();
+    let source_map = transpiled_source
+        .source_map
+        .map(|source_map| source_map.into_bytes().into());
+    Ok((result_source, source_map))
+}
@@ -0,0 +1,198 @@
+use crate::scripts::loader::transpile::{emit_options, transpile_options};
+use data_encoding::HEXLOWER;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::fs::{read, read_dir, remove_file, rename, write, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Folder inside the app cache directory that holds the transpiled modules
+pub const TRANSPILE_CACHE_DIRECTORY: &str = "transpiled";
+
+/// Once the cache grows beyond this, the least recently used entries are removed
+const MAX_CACHE_SIZE: u64 = 64 * 1024 * 1024;
+
+static TRANSPILE_CACHE: OnceLock<TranspileCache> = OnceLock::new();
+static TRANSPILE_SALT: OnceLock<String> = OnceLock::new();
+/// Keeps temporary file names unique between window threads
+static TEMPORARY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Sets up the cache for this process. Runtimes created before this, or when it is never called,
+/// transpile every module again.
+pub fn init_transpile_cache(directory: PathBuf) {
+    if let Err(e) = std::fs::create_dir_all(&directory) {
+        log::warn!(
+            "Could not create transpile cache directory {}: {:?}",
+            directory.display(),
+            e
+        );
+        return;
+    }
+    let _ = TRANSPILE_CACHE.set(TranspileCache { directory });
+}
+
+pub fn transpile_cache() -> Option<&'static TranspileCache> {
+    TRANSPILE_CACHE.get()
+}
+
+/// Identifies how modules are transpiled. Entries made by another version of Snip or deno_ast,
+/// or with other options, can differ from what transpiling produces now, so they are not used.
+fn transpile_salt() -> &'static str {
+    TRANSPILE_SALT.get_or_init(|| {
+        let options = format!("{:?} {:?}", transpile_options(), emit_options());
+        format!(
+            "{}-{}-{}",
+            env!("CARGO_PKG_VERSION"),
+            env!("SNIP_DENO_AST_VERSION"),
+            HEXLOWER.encode(digest(&SHA256, options.as_bytes()).as_ref())
+        )
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    salt: String,
+    path: String,
+    modified: u64,
+    content_hash: String,
+    code: String,
+    source_map: Option<String>,
+}
+
+/// The result of transpiling a module
+pub struct TranspiledModule {
+    pub code: String,
+    pub source_map: Option<String>,
+}
+
+/// Keeps transpiled JavaScript and source maps on disk, so modules only have to be transpiled
+/// again when they change. There is a single entry per source file, which is only used while the
+/// modification time and content hash of the file still match, and the module would still be
+/// transpiled the same way.
+pub struct TranspileCache {
+    directory: PathBuf,
+}
+
+impl TranspileCache {
+    /// Returns the transpiled module for `path` with the given `source`, calling `transpile` and
+    /// storing its result when there is no valid entry.
+    pub fn get_or_transpile<E>(
+        &self,
+        path: &Path,
+        source: &str,
+        transpile: impl FnOnce() -> Result<TranspiledModule, E>,
+    ) -> Result<TranspiledModule, E> {
+        let entry_path = self.entry_path(path);
+        let modified = modified_millis(path);
+        let content_hash = HEXLOWER.encode(digest(&SHA256, source.as_bytes()).as_ref());
+
+        let cached_entry = read(&entry_path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<CacheEntry>(&contents).ok())
+            .filter(|entry| {
+                entry.salt == transpile_salt()
+                    && entry.path == path.to_string_lossy()
+                    && Some(entry.modified) == modified
+                    && entry.content_hash == content_hash
+            });
+        if let Some(entry) = cached_entry {
+            // Marks the entry as recently used, which is what the size cap goes by
+            if let Ok(file) = File::options().append(true).open(&entry_path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(TranspiledModule {
+                code: entry.code,
+                source_map: entry.source_map,
+            });
+        }
+
+        let module = transpile()?;
+        if let Some(modified) = modified {
+            let entry = CacheEntry {
+                salt: transpile_salt().to_string(),
+                path: path.to_string_lossy().to_string(),
+                modified,
+                content_hash,
+                code: module.code,
+                source_map: module.source_map,
+            };
+            self.store(&entry_path, &entry);
+            return Ok(TranspiledModule {
+                code: entry.code,
+                source_map: entry.source_map,
+            });
+        }
+        Ok(module)
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let path_hash = digest(&SHA256, path.to_string_lossy().as_bytes());
+        self.directory
+            .join(format!("{}.json", HEXLOWER.encode(path_hash.as_ref())))
+    }
+
+    fn store(&self, entry_path: &Path, entry: &CacheEntry) {
+        let Ok(contents) = serde_json::to_vec(entry) else {
+            return;
+        };
+        // Runtimes on other windows may read the entry at the same time, so it is written to a
+        // temporary file first and then moved into place
+        let temporary_path = entry_path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let stored =
+            write(&temporary_path, contents).and_then(|_| rename(&temporary_path, entry_path));
+        if let Err(e) = stored {
+            log::warn!(
+                "Could not store transpiled module {}: {:?}",
+                entry_path.display(),
+                e
+            );
+            let _ = remove_file(&temporary_path);
+            return;
+        }
+        self.enforce_size_limit();
+    }
+
+    /// Removes the least recently used entries until the cache fits in `MAX_CACHE_SIZE`
+    fn enforce_size_limit(&self) {
+        let Ok(directory) = read_dir(&self.directory) else {
+            return;
+        };
+        let mut entries: Vec<(PathBuf, u64, SystemTime)> = directory
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+                Some((entry.path(), metadata.len(), modified))
+            })
+            .collect();
+        let mut total_size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total_size <= MAX_CACHE_SIZE {
+            return;
+        }
+
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in entries {
+            if total_size <= MAX_CACHE_SIZE {
+                break;
+            }
+            if remove_file(&path).is_ok() {
+                total_size -= size;
+            }
+        }
+    }
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The script API level implemented by this version of Snip. Commands and libraries declare the
+/// level they were written against in their `api` field.
+pub const SCRIPT_API_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparator {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+/// A requirement on the version of a library, such as `2`, `>=2` or `>=2, <4`. Library versions
+/// are plain numbers, so a bare number only matches that exact version and `*` matches any.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionRequirement {
+    source: String,
+    constraints: Vec<(Comparator, u32)>,
+}
+
+impl VersionRequirement {
+    pub fn matches(&self, version: u32) -> bool {
+        self.constraints
+            .iter()
+            .all(|(comparator, required)| match comparator {
+                Comparator::Equal => version == *required,
+                Comparator::Greater => version > *required,
+                Comparator::GreaterOrEqual => version >= *required,
+                Comparator::Less => version < *required,
+                Comparator::LessOrEqual => version <= *required,
+            })
+    }
+}
+
+impl TryFrom<String> for VersionRequirement {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        let mut constraints = vec![];
+        for part in source.split(',').map(str::trim) {
+            if part == "*" {
+                continue;
+            }
+            let (comparator, version) = if let Some(version) = part.strip_prefix(">=") {
+                (Comparator::GreaterOrEqual, version)
+            } else if let Some(version) = part.strip_prefix("<=") {
+                (Comparator::LessOrEqual, version)
+            } else if let Some(version) = part.strip_prefix('>') {
+                (Comparator::Greater, version)
+            } else if let Some(version) = part.strip_prefix('<') {
+                (Comparator::Less, version)
+            } else if let Some(version) = part.strip_prefix('=') {
+                (Comparator::Equal, version)
+            } else {
+                (Comparator::Equal, part)
+            };
+            let version = version.trim().parse::<u32>().map_err(|_| {
+                format!(
+                    "Invalid version requirement \"{}\", expected something like \">=2\"",
+                    source
+                )
+            })?;
+            constraints.push((comparator, version));
+        }
+        Ok(Self {
+            source,
+            constraints,
+        })
+    }
+}
+
+impl From<VersionRequirement> for String {
+    fn from(requirement: VersionRequirement) -> Self {
+        requirement.source
+    }
+}
+
+impl Display for VersionRequirement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<VersionRequirement, String> {
+        VersionRequirement::try_from(source.to_string())
+    }
+
+    fn matching_versions(requirement: &VersionRequirement) -> Vec<u32> {
+        (0..=6)
+            .filter(|version| requirement.matches(*version))
+            .collect()
+    }
+
+    #[test]
+    fn parses_range() {
+        let requirement = parse(">=2, <4").unwrap();
+
+        assert_eq!(
+            requirement.constraints,
+            vec![(Comparator::GreaterOrEqual, 2), (Comparator::Less, 4)]
+        );
+        assert_eq!(matching_versions(&requirement), vec![2, 3]);
+    }
+
+    #[test]
+    fn parses_exact_version() {
+        assert_eq!(matching_versions(&parse("3").unwrap()), vec![3]);
+        assert_eq!(matching_versions(&parse("=3").unwrap()), vec![3]);
+    }
+
+    #[test]
+    fn parses_every_comparator() {
+        assert_eq!(matching_versions(&parse(">4").unwrap()), vec![5, 6]);
+        assert_eq!(matching_versions(&parse(">=4").unwrap()), vec![4, 5, 6]);
+        assert_eq!(matching_versions(&parse("<2").unwrap()), vec![0, 1]);
+        assert_eq!(matching_versions(&parse("<=2").unwrap()), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parses_wildcard() {
+        let requirement = parse("*").unwrap();
+
+        assert!(requirement.constraints.is_empty());
+        assert_eq!(matching_versions(&requirement), vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn ignores_whitespace() {
+        let requirement = parse("  >= 2 ,<4  ").unwrap();
+
+        assert_eq!(matching_versions(&requirement), vec![2, 3]);
+        // The requirement is shown the way it was written
+        assert_eq!(requirement.to_string(), "  >= 2 ,<4  ");
+    }
+
+    #[test]
+    fn rejects_invalid_requirements() {
+        for source in [
+            "", "latest", ">=", "=>2", "2.1", "-1", ">=2,", "^2", ">=2 <4",
+        ] {
+            assert_eq!(
+                parse(source),
+                Err(format!(
+                    "Invalid version requirement \"{}\", expected something like \">=2\"",
+                    source
+                )),
+                "{:?} should be rejected",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn deserializes_from_string() {
+        let requirement: VersionRequirement = serde_json::from_str("\">=2, <4\"").unwrap();
+        assert_eq!(matching_versions(&requirement), vec![2, 3]);
+
+        let error = serde_json::from_str::<VersionRequirement>("\"two\"").unwrap_err();
+        assert!(error.to_string().contains("Invalid version requirement"));
+    }
+}
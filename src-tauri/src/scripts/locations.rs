@@ -0,0 +1,132 @@
+use crate::scripts::loader::scripts::{ScriptLocationKind, ScriptManager};
+use crate::scripts::watcher::ScriptWatcher;
+use crate::settings::{save_settings, Settings};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use tauri::async_runtime::Mutex;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::oneshot;
+
+pub const USER_SCRIPT_DIRECTORY: &str = "scripts";
+
+pub fn bundled_script_directory(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
+    app.path()
+        .resolve("resources/scripts/", BaseDirectory::Resource)
+}
+
+pub fn user_script_directory(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
+    Ok(app.path().app_config_dir()?.join(USER_SCRIPT_DIRECTORY))
+}
+
+/// Creates the layout of a script location, so users know where to put their own commands and
+/// libraries.
+pub fn ensure_script_directory(location: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(location.join("commands"))?;
+    std::fs::create_dir_all(location.join("library"))?;
+    Ok(())
+}
+
+/// Loads the bundled scripts, the locations configured in the settings and the scripts in the
+/// user script directory, in increasing order of priority.
+pub async fn load_script_locations(
+    script_manager: &mut ScriptManager,
+    bundled_directory: PathBuf,
+    extra_locations: &[PathBuf],
+    user_directory: Option<PathBuf>,
+) {
+    let mut locations = vec![(bundled_directory, ScriptLocationKind::Bundled)];
+    locations.extend(
+        extra_locations
+            .iter()
+            .map(|location| (location.clone(), ScriptLocationKind::Extra)),
+    );
+    if let Some(user_directory) = user_directory {
+        locations.push((user_directory, ScriptLocationKind::User));
+    }
+
+    for (location, kind) in locations {
+        if let Err(e) = script_manager.add_location(location.clone(), kind).await {
+            log::warn!(
+                "Could not load scripts from {}: {:?}",
+                location.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Lets open windows know that the available script commands have changed.
+pub fn emit_scripts_updated(app: &AppHandle) {
+    app.emit("scripts_updated", ())
+        .expect("Could not emit scripts_updated event");
+}
+
+#[tauri::command]
+pub async fn add_script_location(
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    script_manager: State<'_, Mutex<ScriptManager>>,
+    script_watcher: State<'_, Mutex<ScriptWatcher>>,
+    webview_window: tauri::WebviewWindow,
+) -> Result<Settings, String> {
+    let (sender, receiver) = oneshot::channel::<Option<FilePath>>();
+    app.dialog()
+        .file()
+        .set_parent(&webview_window)
+        .set_title("Add script location")
+        .pick_folder(move |path| {
+            let _ = sender.send(path);
+        });
+    let location = receiver
+        .await
+        .ok()
+        .flatten()
+        .and_then(|path| path.into_path().ok());
+
+    let mut settings = settings.lock().await;
+    let Some(location) = location else {
+        return Ok(settings.clone());
+    };
+    if settings.script_locations().contains(&location) {
+        return Ok(settings.clone());
+    }
+
+    script_manager
+        .lock()
+        .await
+        .add_location(location.clone(), ScriptLocationKind::Extra)
+        .await
+        .map_err(|e| format!("Could not load scripts from {}: {}", location.display(), e))?;
+    script_watcher.lock().await.watch(&location);
+    settings.add_script_location(location);
+    emit_scripts_updated(&app);
+    save_settings(app, &settings).map_err(|_| "Could not save settings".to_string())
+}
+
+#[tauri::command]
+pub async fn remove_script_location(
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    script_manager: State<'_, Mutex<ScriptManager>>,
+    script_watcher: State<'_, Mutex<ScriptWatcher>>,
+    location: PathBuf,
+) -> Result<Settings, String> {
+    let mut settings = settings.lock().await;
+    settings.remove_script_location(&location);
+    script_manager.lock().await.remove_location(&location);
+    script_watcher.lock().await.unwatch(&location);
+    emit_scripts_updated(&app);
+    save_settings(app, &settings).map_err(|_| "Could not save settings".to_string())
+}
+
+#[tauri::command]
+pub async fn open_user_script_directory(app: AppHandle) -> Result<(), String> {
+    let location = user_script_directory(&app).map_err(|e| e.to_string())?;
+    ensure_script_directory(&location).map_err(|e| e.to_string())?;
+    app.opener()
+        .open_path(location.to_string_lossy(), None::<&str>)
+        .map_err(|e| e.to_string())
+}
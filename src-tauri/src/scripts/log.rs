@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many runs are kept per window
+const MAX_LOGGED_RUNS: usize = 5;
+/// Lines beyond this are dropped, so a script logging in a loop cannot exhaust memory
+const MAX_LOG_ENTRIES_PER_RUN: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptLogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl ScriptLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptLogLevel::Debug => "debug",
+            ScriptLogLevel::Info => "info",
+            ScriptLogLevel::Warn => "warn",
+            ScriptLogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptLogEntry {
+    pub level: ScriptLogLevel,
+    pub message: String,
+    /// Milliseconds since the unix epoch
+    pub timestamp: u64,
+}
+
+impl ScriptLogEntry {
+    pub fn new(level: ScriptLogLevel, message: String) -> Self {
+        Self {
+            level,
+            message,
+            timestamp: now_millis(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRunLog {
+    pub command_id: String,
+    pub command_title: String,
+    pub started_at: u64,
+    pub entries: Vec<ScriptLogEntry>,
+    /// Number of entries that were dropped because the run logged too much
+    pub dropped_entries: usize,
+}
+
+/// The console output of the last few script runs in a window, newest run last.
+#[derive(Default)]
+pub struct ScriptLogs {
+    runs: VecDeque<ScriptRunLog>,
+}
+
+impl ScriptLogs {
+    pub fn start_run(&mut self, command_id: String, command_title: String) {
+        if self.runs.len() == MAX_LOGGED_RUNS {
+            self.runs.pop_front();
+        }
+        self.runs.push_back(ScriptRunLog {
+            command_id,
+            command_title,
+            started_at: now_millis(),
+            entries: vec![],
+            dropped_entries: 0,
+        });
+    }
+
+    /// Adds an entry to the current run
+    pub fn push(&mut self, entry: ScriptLogEntry) {
+        if let Some(run) = self.runs.back_mut() {
+            if run.entries.len() < MAX_LOG_ENTRIES_PER_RUN {
+                run.entries.push(entry);
+            } else {
+                run.dropped_entries += 1;
+            }
+        }
+    }
+
+    pub fn runs(&self) -> Vec<ScriptRunLog> {
+        self.runs.iter().cloned().collect()
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,298 @@
+use crate::scripts::commands::{
+    InternalScriptRunEditorRequest, InternalScriptRunEditorResponse, ScriptRunEditorRequestEvent,
+};
+use crate::scripts::loader::scripts::{
+    EditorDocumentInfo, EditorSelection, EditorSelectionReplacement, EditorSelectionState,
+    EditorState,
+};
+
+/// Converts an offset in UTF-16 code units, which is how both CodeMirror and JavaScript strings
+/// count positions, into a byte offset in `text`. Offsets past the end are clamped to the end,
+/// offsets within a surrogate pair are rounded down to the start of the character.
+pub fn utf16_to_byte_offset(text: &str, offset: usize) -> usize {
+    let mut utf16_offset = 0;
+    for (byte_offset, c) in text.char_indices() {
+        utf16_offset += c.len_utf16();
+        if utf16_offset > offset {
+            return byte_offset;
+        }
+    }
+    text.len()
+}
+
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Maps a position through the replacement of `start..end` with `inserted` code units, the way
+/// CodeMirror maps positions through a change. Positions within the replaced range move to its
+/// start, or to the end of the inserted text when `after` is set and they are not at the start.
+fn map_position(position: usize, start: usize, end: usize, inserted: usize, after: bool) -> usize {
+    if position < start {
+        position
+    } else if position < end || (position == end && start == end && !after) {
+        if position == start || !after {
+            start
+        } else {
+            start + inserted
+        }
+    } else {
+        position - (end - start) + inserted
+    }
+}
+
+/// A document that lives in memory instead of in a webview, used to run scripts without a window.
+/// It answers the same editor requests the frontend does, so scripts cannot tell the difference.
+pub struct MemoryEditor {
+    text: String,
+    selection: EditorSelectionState,
+    language: Option<String>,
+}
+
+impl MemoryEditor {
+    pub fn new(text: String) -> Self {
+        Self::with_selection(
+            text,
+            EditorSelectionState {
+                main_selection_index: 0,
+                selections: vec![EditorSelection::cursor(0)],
+            },
+        )
+    }
+
+    pub fn with_selection(text: String, selection: EditorSelectionState) -> Self {
+        Self {
+            text,
+            selection,
+            language: None,
+        }
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    pub fn editor_state(&self) -> EditorState {
+        EditorState {
+            selection: self.selection.clone(),
+            document: EditorDocumentInfo {
+                language: self.language.clone(),
+                length: utf16_len(&self.text),
+                line_count: self.text.split('\n').count(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The line and column of a position, both counting from 1 like editors show them
+    pub fn line_and_column(&self, position: usize) -> (usize, usize) {
+        let before = self.slice(0, position);
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (
+            before.matches('\n').count() + 1,
+            utf16_len(&before[line_start..]) + 1,
+        )
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &str {
+        let start = utf16_to_byte_offset(&self.text, start);
+        let end = utf16_to_byte_offset(&self.text, end).max(start);
+        &self.text[start..end]
+    }
+
+    /// Applies a request to the document. Requests that do not concern the document, such as
+    /// errors reported by the script, are handed back to the caller.
+    pub fn handle_request(
+        &mut self,
+        request: InternalScriptRunEditorRequest,
+    ) -> Option<InternalScriptRunEditorRequest> {
+        match request {
+            InternalScriptRunEditorRequest::Request(event) => self
+                .apply(event)
+                .err()
+                .map(InternalScriptRunEditorRequest::Request),
+            InternalScriptRunEditorRequest::RequestWithResponse {
+                event,
+                reply_sender,
+            } => match self.respond(event) {
+                Ok(response) => {
+                    let _ = reply_sender.send(response);
+                    None
+                }
+                Err(event) => Some(InternalScriptRunEditorRequest::RequestWithResponse {
+                    event,
+                    reply_sender,
+                }),
+            },
+            request => Some(request),
+        }
+    }
+
+    fn apply(
+        &mut self,
+        event: ScriptRunEditorRequestEvent,
+    ) -> Result<(), ScriptRunEditorRequestEvent> {
+        match event {
+            ScriptRunEditorRequestEvent::SetFullText(text) => {
+                self.text = text;
+                let end = utf16_len(&self.text);
+                for selection in &mut self.selection.selections {
+                    selection.anchor = selection.anchor.min(end);
+                    selection.head = selection.head.min(end);
+                    selection.text = None;
+                }
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::ReplaceSelections(replacements) => {
+                self.replace_selections(replacements);
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::AppendText(text) => {
+                let end = utf16_len(&self.text);
+                self.replace_range(end, end, &text);
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::InsertAt { position, text } => {
+                self.replace_range(position, position, &text);
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::ReplaceRange { start, end, text } => {
+                self.replace_range(start, end, &text);
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::SetSelections(mut selection) => {
+                let end = utf16_len(&self.text);
+                for selection in &mut selection.selections {
+                    selection.anchor = selection.anchor.min(end);
+                    selection.head = selection.head.min(end);
+                    selection.text = None;
+                }
+                self.selection = selection;
+                Ok(())
+            }
+            ScriptRunEditorRequestEvent::SetLanguage(language) => {
+                self.language = Some(language);
+                Ok(())
+            }
+            event => Err(event),
+        }
+    }
+
+    fn respond(
+        &self,
+        event: ScriptRunEditorRequestEvent,
+    ) -> Result<Result<InternalScriptRunEditorResponse, String>, ScriptRunEditorRequestEvent> {
+        match event {
+            ScriptRunEditorRequestEvent::GetFullText => Ok(Ok(
+                InternalScriptRunEditorResponse::GetFullText(self.text.clone()),
+            )),
+            ScriptRunEditorRequestEvent::GetCursor => {
+                Ok(Ok(InternalScriptRunEditorResponse::GetCursor(
+                    self.selection
+                        .selections
+                        .get(self.selection.main_selection_index)
+                        .map_or(0, |selection| selection.head),
+                )))
+            }
+            ScriptRunEditorRequestEvent::GetDocumentLength => Ok(Ok(
+                InternalScriptRunEditorResponse::GetDocumentLength(utf16_len(&self.text)),
+            )),
+            ScriptRunEditorRequestEvent::GetPartialText {
+                start,
+                end,
+                selection_index,
+            } => {
+                let range = match (selection_index, start, end) {
+                    (Some(index), _, _) => self
+                        .selection
+                        .selections
+                        .get(index)
+                        .map(|selection| (selection.start(), selection.end())),
+                    (None, Some(start), Some(end)) => Some((start.min(end), start.max(end))),
+                    _ => None,
+                };
+                Ok(range
+                    .map(|(start, end)| {
+                        InternalScriptRunEditorResponse::GetPartialText(
+                            self.slice(start, end).to_string(),
+                        )
+                    })
+                    .ok_or_else(|| {
+                        "getPartialText: expects either an available selectionIndex or a combination of start and end index".to_string()
+                    }))
+            }
+            event => Err(event),
+        }
+    }
+
+    /// Replaces a range of the document and maps the selections through the change, like the
+    /// frontend does when a script inserts or replaces text
+    fn replace_range(&mut self, start: usize, end: usize, text: &str) {
+        let start_byte = utf16_to_byte_offset(&self.text, start);
+        let end_byte = utf16_to_byte_offset(&self.text, end).max(start_byte);
+        let start = utf16_len(&self.text[..start_byte]);
+        let end = start + utf16_len(&self.text[start_byte..end_byte]);
+        self.text.replace_range(start_byte..end_byte, text);
+
+        let inserted = utf16_len(text);
+        for selection in &mut self.selection.selections {
+            let (from, to) = if selection.anchor == selection.head {
+                let position = map_position(selection.head, start, end, inserted, false);
+                (position, position)
+            } else {
+                let from = map_position(selection.start(), start, end, inserted, true);
+                let to = map_position(selection.end(), start, end, inserted, false);
+                (from, to.max(from))
+            };
+            if selection.anchor <= selection.head {
+                selection.anchor = from;
+                selection.head = to;
+            } else {
+                selection.anchor = to;
+                selection.head = from;
+            }
+            selection.text = None;
+        }
+    }
+
+    /// Mirrors how the frontend replaces selections: every replaced selection becomes a cursor
+    /// after the inserted text, and all following selections shift along with the edit.
+    fn replace_selections(&mut self, replacements: Vec<EditorSelectionReplacement>) {
+        let mut texts: Vec<Option<String>> = vec![None; self.selection.selections.len()];
+        for replacement in replacements {
+            if let Some(text) = texts.get_mut(replacement.index) {
+                *text = Some(replacement.text);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..self.selection.selections.len()).collect();
+        order.sort_by_key(|index| self.selection.selections[*index].start());
+
+        let mut result = String::with_capacity(self.text.len());
+        let mut copied_until = 0;
+        let mut shift: isize = 0;
+        for index in order {
+            let selection = &mut self.selection.selections[index];
+            let (start, end) = (selection.start(), selection.end());
+            match texts[index].take() {
+                Some(text) => {
+                    let start_byte = utf16_to_byte_offset(&self.text, start).max(copied_until);
+                    let end_byte = utf16_to_byte_offset(&self.text, end).max(start_byte);
+                    result.push_str(&self.text[copied_until..start_byte]);
+                    result.push_str(&text);
+                    copied_until = end_byte;
+
+                    shift += utf16_len(&text) as isize - (end - start) as isize;
+                    let cursor = (end as isize + shift) as usize;
+                    *selection = EditorSelection::cursor(cursor);
+                }
+                None => {
+                    selection.anchor = (selection.anchor as isize + shift) as usize;
+                    selection.head = (selection.head as isize + shift) as usize;
+                }
+            }
+        }
+        result.push_str(&self.text[copied_until..]);
+        self.text = result;
+    }
+}
@@ -1,2 +1,17 @@
+pub mod abort;
+pub mod annotations;
 pub mod commands;
+pub mod crypto;
+pub mod diff;
+pub mod edit_transaction;
+pub mod error;
 pub mod loader;
+pub mod locations;
+pub mod log;
+pub mod memory_editor;
+pub mod permissions;
+pub mod runtime;
+#[cfg(test)]
+mod snapshot;
+pub mod watcher;
+pub mod web;
@@ -0,0 +1,161 @@
+use crate::document::confirm;
+use crate::scripts::loader::scripts::Command;
+use crate::settings::{save_settings, Settings};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use tauri::async_runtime::Mutex;
+use tauri::AppHandle;
+
+/// The files a command may access, as declared in the `permissions` field of its command.json.
+/// Paths may start with `~` for the home directory, relative paths are relative to the command
+/// folder. A path grants access to everything inside of it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScriptPermissions {
+    #[serde(default)]
+    pub read: Vec<String>,
+    #[serde(default)]
+    pub write: Vec<String>,
+}
+
+impl ScriptPermissions {
+    pub fn is_empty(&self) -> bool {
+        self.read.is_empty() && self.write.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        let mut lines = vec![];
+        for path in &self.read {
+            lines.push(format!("Read {}", path));
+        }
+        for path in &self.write {
+            lines.push(format!("Write {}", path));
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileAccessKind {
+    Read,
+    Write,
+}
+
+/// The paths a running script was granted access to, resolved against the command folder.
+pub struct FileAccess {
+    base: PathBuf,
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+}
+
+impl FileAccess {
+    pub fn new(permissions: &ScriptPermissions, command_location: &Path) -> Self {
+        let resolve = |paths: &[String]| {
+            paths
+                .iter()
+                .map(|path| resolve_path(path, command_location))
+                .collect()
+        };
+        Self {
+            base: command_location.to_path_buf(),
+            read: resolve(&permissions.read),
+            write: resolve(&permissions.write),
+        }
+    }
+
+    /// Resolves a path given by the script and checks it against the grants. Returns the
+    /// resolved path, or an error that can be shown to the script.
+    pub fn check(&self, path: &str, kind: FileAccessKind) -> Result<PathBuf, String> {
+        let resolved_path = resolve_path(path, &self.base);
+        let grants = match kind {
+            FileAccessKind::Read => &self.read,
+            FileAccessKind::Write => &self.write,
+        };
+        if grants.iter().any(|grant| resolved_path.starts_with(grant)) {
+            Ok(resolved_path)
+        } else {
+            let access = match kind {
+                FileAccessKind::Read => "read",
+                FileAccessKind::Write => "write",
+            };
+            Err(format!(
+                "The command is not allowed to {} {}. Add it to the \"{}\" permissions in command.json",
+                access,
+                resolved_path.display(),
+                access
+            ))
+        }
+    }
+}
+
+/// Expands `~`, makes the path absolute and resolves `.` and `..`. Symbolic links are resolved
+/// for the part of the path that exists, so a link inside a granted folder cannot be used to
+/// reach files outside of it.
+fn resolve_path(path: &str, base: &Path) -> PathBuf {
+    let path = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home_dir) => home_dir.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    };
+    let path = base.join(path);
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    let mut existing = normalized.clone();
+    let mut missing: Vec<OsString> = vec![];
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        match existing.file_name() {
+            Some(name) => missing.push(name.to_os_string()),
+            None => return normalized,
+        }
+        if !existing.pop() {
+            return normalized;
+        }
+    }
+}
+
+/// Asks the user to allow the file access a command declares, unless it was allowed before.
+/// Consent is remembered per command, until the command asks for different permissions or any of
+/// its files change.
+pub async fn ensure_permissions_granted(
+    app: &AppHandle,
+    settings: &Mutex<Settings>,
+    command: &Command,
+) -> bool {
+    let permissions = &command.info.permissions;
+    if settings.lock().await.has_granted_permissions(command) {
+        return true;
+    }
+
+    let message = format!(
+        "The command \"{}\" ({}) wants to access files on your computer:\n\n{}\n\nOnly allow this for scripts you trust.",
+        command.info.title,
+        command.id,
+        permissions.describe()
+    );
+    if !confirm(app, "Allow file access?", message, "Allow").await {
+        return false;
+    }
+
+    let mut settings = settings.lock().await;
+    settings.grant_permissions(command);
+    let _ = save_settings(app.clone(), &settings);
+    true
+}
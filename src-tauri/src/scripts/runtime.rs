@@ -0,0 +1,100 @@
+use crate::scripts::commands::snip;
+use crate::scripts::loader::js_runtime::SnipModuleLoader;
+#[cfg(feature = "bench")]
+use crate::scripts::loader::transpile::transpile_extension;
+use deno_core::{JsRuntime, RuntimeOptions};
+use std::rc::Rc;
+
+/// A V8 snapshot of a runtime with the `snip` extension already transpiled and evaluated, created
+/// by the build script
+static SNAPSHOT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/SNIP_SNAPSHOT.bin"));
+
+/// A runtime that has not run any script yet, together with the loader it will load the command
+/// with.
+pub struct ScriptRuntime {
+    pub js_runtime: JsRuntime,
+    pub module_loader: Rc<SnipModuleLoader>,
+}
+
+impl ScriptRuntime {
+    /// Creates a runtime by transpiling and evaluating the `snip` extension from source
+    #[cfg(feature = "bench")]
+    pub fn from_source() -> Self {
+        let module_loader = Rc::new(SnipModuleLoader::new());
+        let js_runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(module_loader.clone()),
+            extension_transpiler: Some(Rc::new(transpile_extension)),
+            extensions: vec![snip::init_ops_and_esm()],
+            ..Default::default()
+        });
+        Self {
+            js_runtime,
+            module_loader,
+        }
+    }
+
+    /// Creates a runtime from the snapshot, which skips transpiling and evaluating the extension.
+    pub fn from_snapshot() -> Self {
+        let module_loader = Rc::new(SnipModuleLoader::new());
+        let js_runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(module_loader.clone()),
+            startup_snapshot: Some(SNAPSHOT),
+            extensions: vec![snip::init_ops()],
+            ..Default::default()
+        });
+        Self {
+            js_runtime,
+            module_loader,
+        }
+    }
+}
+
+/// Keeps a runtime ready on a window thread, so starting a script does not have to wait for a
+/// runtime to be created. A runtime is only used for a single run, since scripts can leave state
+/// behind in the global scope.
+#[derive(Default)]
+pub struct ScriptRuntimePool {
+    prepared: Option<ScriptRuntime>,
+}
+
+impl ScriptRuntimePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn take(&mut self) -> ScriptRuntime {
+        self.prepared
+            .take()
+            .unwrap_or_else(ScriptRuntime::from_snapshot)
+    }
+
+    /// Prepares the runtime for the next run. Call this while the thread is otherwise idle.
+    pub fn refill(&mut self) {
+        if self.prepared.is_none() {
+            self.prepared = Some(ScriptRuntime::from_snapshot());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scripts::commands::snip;
+    use crate::scripts::snapshot;
+    use deno_core::Extension;
+
+    fn op_signatures(mut extension: Extension) -> Vec<(&'static str, bool, u8)> {
+        extension
+            .init_ops()
+            .iter()
+            .map(|op| (op.name, op.is_async, op.arg_count))
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_ops_match_the_extension() {
+        assert_eq!(
+            op_signatures(snapshot::snip::init_ops()),
+            op_signatures(snip::init_ops())
+        );
+    }
+}
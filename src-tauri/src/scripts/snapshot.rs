@@ -0,0 +1,77 @@
+use deno_core::{extension, op2};
+
+/// Declares the `snip` extension with stand-ins for its ops, for the build script to create the
+/// startup snapshot with. The real ops depend on most of the `scripts` module, which the build
+/// script cannot compile. Runtimes started from the snapshot register the real ops over the
+/// stand-ins, which only works if the stand-ins have the same names, order, async-ness and number
+/// of arguments as the ops in `commands.rs`.
+macro_rules! stand_in_extension {
+    ($($kind:ident $op:ident($($arg:ident),*);)*) => {
+        $(stand_in_op!($kind $op($($arg),*));)*
+
+        extension!(
+            snip,
+            ops = [$($op),*],
+            esm_entry_point = "ext:snip/index.ts",
+            esm = [dir "js_runtime/snip", "index.ts"]
+        );
+    };
+}
+
+macro_rules! stand_in_op {
+    (sync $op:ident($($arg:ident),*)) => {
+        #[op2(nofast)]
+        fn $op($(#[smi] $arg: u32),*) {
+            let _ = ($($arg,)*);
+        }
+    };
+    (async $op:ident($($arg:ident),*)) => {
+        #[op2(async)]
+        async fn $op($(#[smi] $arg: u32),*) {
+            let _ = ($($arg,)*);
+        }
+    };
+}
+
+stand_in_extension! {
+    async snip_op_get_full_text(editor_handle);
+    async snip_op_set_full_text(editor_handle, full_text);
+    async snip_op_set_error(editor_handle, error);
+    async snip_op_set_status(editor_handle, level, message, duration);
+    async snip_op_get_partial_text(editor_handle, start, end);
+    async snip_op_get_selection_state(editor_handle);
+    async snip_op_get_document_info(editor_handle);
+    async snip_op_get_parameters(editor_handle);
+    async snip_op_replace_selections(editor_handle, replacements);
+    async snip_op_get_document_length(editor_handle);
+    async snip_op_open_document_reader(editor_handle, start, end, chunk_length);
+    async snip_op_read_document_chunk(reader_id);
+    async snip_op_append_text(editor_handle, text);
+    async snip_op_set_selections(editor_handle, selection_state);
+    async snip_op_insert_at(editor_handle, position, text);
+    async snip_op_replace_range(editor_handle, start, end, text);
+    async snip_op_get_cursor(editor_handle);
+    async snip_op_set_language(editor_handle, language);
+    async snip_op_set_annotations(editor_handle, annotations);
+    async snip_op_log(editor_handle, level, message);
+    async snip_op_prompt(editor_handle, message, default_value);
+    async snip_op_confirm(editor_handle, message);
+    async snip_op_pick_from_list(editor_handle, message, options);
+    async snip_op_read_text_file(path);
+    async snip_op_write_text_file(path, contents);
+    sync op_base64_atob(s);
+    sync op_base64_btoa(s);
+    sync op_snip_crypto_digest(algorithm, data);
+    sync op_snip_crypto_hmac(algorithm, key, data);
+    sync op_snip_crypto_random_bytes(output);
+    sync op_snip_encode(encoding_name, data);
+    sync op_snip_decode(encoding_name, text);
+    sync op_snip_text_encode_into(source, destination);
+    sync op_snip_text_decode(encoding, data, fatal, ignore_bom);
+    sync op_snip_url_parse(href, base);
+    sync op_snip_url_set(href, component, value);
+    sync op_snip_url_parse_search_params(query);
+    sync op_snip_url_stringify_search_params(params);
+    sync op_snip_timer_create();
+    async op_snip_timer_sleep(timer_id, millis);
+}
@@ -0,0 +1,87 @@
+use crate::scripts::loader::scripts::ScriptManager;
+use crate::scripts::locations::emit_scripts_updated;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::async_runtime::{spawn, Mutex};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+/// Editors usually save a file in several steps, so changes are collected for a short while
+/// before the scripts are reloaded.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Watches the script locations and reloads the scripts of a location whenever something inside
+/// of it changes. Once scripts are reloaded, a `scripts_updated` event is emitted to all windows.
+/// When the platform watcher cannot be created, the watcher does nothing and scripts are only
+/// loaded on startup.
+pub struct ScriptWatcher {
+    debouncer: Option<Debouncer<RecommendedWatcher>>,
+}
+
+impl ScriptWatcher {
+    pub fn new(app: &AppHandle) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+        // The watcher reports changes on its own thread, so they are forwarded to the async runtime
+        let event_handler = move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let paths = events.into_iter().map(|event| event.path).collect();
+                let _ = sender.send(paths);
+            }
+            Err(e) => log::error!("Error while watching script locations: {:?}", e),
+        };
+        let debouncer = match new_debouncer(DEBOUNCE_TIMEOUT, event_handler) {
+            Ok(debouncer) => debouncer,
+            Err(e) => {
+                log::error!("Could not watch script locations for changes: {:?}", e);
+                return Self { debouncer: None };
+            }
+        };
+
+        let app = app.clone();
+        spawn(async move {
+            while let Some(mut changed_paths) = receiver.recv().await {
+                // Pick up changes that were reported while the previous reload was running
+                while let Ok(paths) = receiver.try_recv() {
+                    changed_paths.extend(paths);
+                }
+                let script_manager: State<'_, Mutex<ScriptManager>> = app.state();
+                let reloaded = script_manager
+                    .lock()
+                    .await
+                    .reload_changed_locations(&changed_paths)
+                    .await;
+                if reloaded {
+                    emit_scripts_updated(&app);
+                }
+            }
+        });
+
+        Self {
+            debouncer: Some(debouncer),
+        }
+    }
+
+    pub fn watch(&mut self, location: &Path) {
+        let Some(debouncer) = &mut self.debouncer else {
+            return;
+        };
+        if let Err(e) = debouncer
+            .watcher()
+            .watch(location, RecursiveMode::Recursive)
+        {
+            log::warn!(
+                "Could not watch script location {}: {:?}",
+                location.display(),
+                e
+            );
+        }
+    }
+
+    pub fn unwatch(&mut self, location: &Path) {
+        if let Some(debouncer) = &mut self.debouncer {
+            let _ = debouncer.watcher().unwatch(location);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use deno_core::error::AnyError;
+use deno_core::url::{form_urlencoded, quirks, Url};
+use deno_core::{op2, CancelFuture, CancelHandle, OpState, Resource, ResourceId};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Encodes as much of `source` into `destination` as fits without splitting a character. Returns
+/// the number of UTF-16 code units read and the number of bytes written, like
+/// `TextEncoder.encodeInto`.
+#[op2]
+#[serde]
+pub fn op_snip_text_encode_into(
+    #[string] source: &str,
+    #[buffer] destination: &mut [u8],
+) -> (usize, usize) {
+    let mut read = 0;
+    let mut written = 0;
+    for c in source.chars() {
+        let length = c.len_utf8();
+        if written + length > destination.len() {
+            break;
+        }
+        c.encode_utf8(&mut destination[written..written + length]);
+        written += length;
+        read += c.len_utf16();
+    }
+    (read, written)
+}
+
+/// Decodes `data` with one of the encodings `TextDecoder` supports. Invalid input is replaced
+/// with U+FFFD, unless `fatal` is set.
+#[op2]
+#[string]
+pub fn op_snip_text_decode(
+    #[string] encoding: &str,
+    #[buffer] data: &[u8],
+    fatal: bool,
+    ignore_bom: bool,
+) -> Result<String, AnyError> {
+    let invalid = || AnyError::msg(format!("The encoded data was not valid {}", encoding));
+    match encoding {
+        "utf-8" => {
+            let data = match data {
+                [0xEF, 0xBB, 0xBF, rest @ ..] if !ignore_bom => rest,
+                data => data,
+            };
+            if fatal {
+                std::str::from_utf8(data)
+                    .map(str::to_string)
+                    .map_err(|_| invalid())
+            } else {
+                Ok(String::from_utf8_lossy(data).into_owned())
+            }
+        }
+        "utf-16le" | "utf-16be" => {
+            if fatal && !data.len().is_multiple_of(2) {
+                return Err(invalid());
+            }
+            let big_endian = encoding == "utf-16be";
+            let units = data.chunks(2).map(|bytes| match (bytes, big_endian) {
+                ([high, low], true) | ([low, high], false) => u16::from_be_bytes([*high, *low]),
+                // A trailing odd byte can only be replaced
+                _ => 0xFFFD,
+            });
+            let mut text = String::with_capacity(data.len() / 2);
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok(c) => text.push(c),
+                    Err(_) if fatal => return Err(invalid()),
+                    Err(_) => text.push(char::REPLACEMENT_CHARACTER),
+                }
+            }
+            if !ignore_bom && text.starts_with('\u{FEFF}') {
+                text.remove(0);
+            }
+            Ok(text)
+        }
+        _ => Err(AnyError::msg(format!("Unsupported encoding: {}", encoding))),
+    }
+}
+
+/// The components of a URL as exposed by the `URL` class
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlComponents {
+    href: String,
+    origin: String,
+    protocol: String,
+    username: String,
+    password: String,
+    host: String,
+    hostname: String,
+    port: String,
+    pathname: String,
+    search: String,
+    hash: String,
+}
+
+impl From<&Url> for UrlComponents {
+    fn from(url: &Url) -> Self {
+        Self {
+            href: quirks::href(url).to_string(),
+            origin: quirks::origin(url),
+            protocol: quirks::protocol(url).to_string(),
+            username: quirks::username(url).to_string(),
+            password: quirks::password(url).to_string(),
+            host: quirks::host(url).to_string(),
+            hostname: quirks::hostname(url).to_string(),
+            port: quirks::port(url).to_string(),
+            pathname: quirks::pathname(url).to_string(),
+            search: quirks::search(url).to_string(),
+            hash: quirks::hash(url).to_string(),
+        }
+    }
+}
+
+fn parse_url(href: &str, base: Option<&str>) -> Result<Url, AnyError> {
+    let base = base
+        .map(Url::parse)
+        .transpose()
+        .map_err(|_| AnyError::msg(format!("Invalid base URL: {}", base.unwrap_or_default())))?;
+    Url::options()
+        .base_url(base.as_ref())
+        .parse(href)
+        .map_err(|_| AnyError::msg(format!("Invalid URL: {}", href)))
+}
+
+#[op2]
+#[serde]
+pub fn op_snip_url_parse(
+    #[string] href: &str,
+    #[string] base: Option<String>,
+) -> Result<UrlComponents, AnyError> {
+    Ok(UrlComponents::from(&parse_url(href, base.as_deref())?))
+}
+
+/// Changes a single component of a URL. Invalid values are ignored, like browsers do, except for
+/// the href itself.
+#[op2]
+#[serde]
+pub fn op_snip_url_set(
+    #[string] href: &str,
+    #[string] component: &str,
+    #[string] value: &str,
+) -> Result<UrlComponents, AnyError> {
+    let mut url = parse_url(href, None)?;
+    match component {
+        "href" => {
+            quirks::set_href(&mut url, value)
+                .map_err(|_| AnyError::msg(format!("Invalid URL: {}", value)))?;
+        }
+        "protocol" => {
+            let _ = quirks::set_protocol(&mut url, value);
+        }
+        "username" => {
+            let _ = quirks::set_username(&mut url, value);
+        }
+        "password" => {
+            let _ = quirks::set_password(&mut url, value);
+        }
+        "host" => {
+            let _ = quirks::set_host(&mut url, value);
+        }
+        "hostname" => {
+            let _ = quirks::set_hostname(&mut url, value);
+        }
+        "port" => {
+            let _ = quirks::set_port(&mut url, value);
+        }
+        "pathname" => quirks::set_pathname(&mut url, value),
+        "search" => quirks::set_search(&mut url, value),
+        "hash" => quirks::set_hash(&mut url, value),
+        _ => {
+            return Err(AnyError::msg(format!(
+                "Unknown URL component: {}",
+                component
+            )))
+        }
+    }
+    Ok(UrlComponents::from(&url))
+}
+
+#[op2]
+#[serde]
+pub fn op_snip_url_parse_search_params(#[string] query: &str) -> Vec<(String, String)> {
+    let query = query.strip_prefix('?').unwrap_or(query);
+    form_urlencoded::parse(query.as_bytes())
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect()
+}
+
+#[op2]
+#[string]
+pub fn op_snip_url_stringify_search_params(#[serde] params: Vec<(String, String)>) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params.iter().map(|(name, value)| (name, value)))
+        .finish()
+}
+
+/// A pending `setTimeout` or `setInterval`. Closing the resource cancels the timer, so a cleared
+/// timer does not keep the script running until it would have fired.
+struct TimerResource {
+    cancel_handle: Rc<CancelHandle>,
+}
+
+impl Resource for TimerResource {
+    fn name(&self) -> Cow<'_, str> {
+        "snipTimer".into()
+    }
+
+    fn close(self: Rc<Self>) {
+        self.cancel_handle.cancel();
+    }
+}
+
+#[op2(fast)]
+#[smi]
+pub fn op_snip_timer_create(state: &mut OpState) -> ResourceId {
+    state.resource_table.add(TimerResource {
+        cancel_handle: CancelHandle::new_rc(),
+    })
+}
+
+/// Waits for `millis` milliseconds. Resolves to false when the timer was cleared before or while
+/// waiting.
+#[op2(async)]
+pub async fn op_snip_timer_sleep(
+    state: Rc<RefCell<OpState>>,
+    #[smi] timer_id: ResourceId,
+    #[number] millis: u64,
+) -> bool {
+    let Ok(timer) = state.borrow().resource_table.get::<TimerResource>(timer_id) else {
+        return false;
+    };
+    tokio::time::sleep(Duration::from_millis(millis))
+        .or_cancel(timer.cancel_handle.clone())
+        .await
+        .is_ok()
+}
@@ -1,6 +1,11 @@
+use crate::scripts::loader::scripts::Command;
+use crate::scripts::permissions::ScriptPermissions;
 use crate::window::menu;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tauri::async_runtime::Mutex;
 use tauri::Error::WebviewLabelAlreadyExists;
 use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
@@ -20,6 +25,23 @@ pub struct Settings {
     preferred_language: String,
     #[serde(default = "settings_default_wrap_lines")]
     wrap_lines: bool,
+    #[serde(default = "settings_default_script_timeout_seconds")]
+    script_timeout_seconds: u64,
+    #[serde(default)]
+    script_locations: Vec<PathBuf>,
+    /// The file access the user allowed, by command id
+    #[serde(default)]
+    granted_permissions: HashMap<String, GrantedPermissions>,
+}
+
+/// File access the user allowed a command, as long as the files of the command do not change
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+struct GrantedPermissions {
+    #[serde(flatten)]
+    permissions: ScriptPermissions,
+    /// Consent given before the files of commands were hashed has no hash, and is asked again
+    #[serde(default)]
+    files_hash: String,
 }
 
 fn settings_default_preferred_language() -> String {
@@ -28,6 +50,9 @@ fn settings_default_preferred_language() -> String {
 fn settings_default_wrap_lines() -> bool {
     false
 }
+fn settings_default_script_timeout_seconds() -> u64 {
+    30
+}
 
 impl Default for Settings {
     fn default() -> Self {
@@ -35,10 +60,57 @@ impl Default for Settings {
             theme: Theme::System,
             preferred_language: settings_default_preferred_language(),
             wrap_lines: settings_default_wrap_lines(),
+            script_timeout_seconds: settings_default_script_timeout_seconds(),
+            script_locations: vec![],
+            granted_permissions: HashMap::new(),
         }
     }
 }
 
+impl Settings {
+    pub fn script_timeout(&self) -> Duration {
+        Duration::from_secs(self.script_timeout_seconds)
+    }
+
+    pub fn script_locations(&self) -> &[PathBuf] {
+        &self.script_locations
+    }
+
+    pub fn add_script_location(&mut self, location: PathBuf) {
+        if !self.script_locations.contains(&location) {
+            self.script_locations.push(location);
+        }
+    }
+
+    pub fn remove_script_location(&mut self, location: &Path) {
+        self.script_locations
+            .retain(|existing| existing.as_path() != location);
+    }
+
+    /// Whether the user allowed the command exactly the permissions it declares, and the files
+    /// of the command did not change since
+    pub fn has_granted_permissions(&self, command: &Command) -> bool {
+        let permissions = &command.info.permissions;
+        permissions.is_empty()
+            || self
+                .granted_permissions
+                .get(&command.id)
+                .is_some_and(|granted| {
+                    &granted.permissions == permissions && granted.files_hash == command.files_hash
+                })
+    }
+
+    pub fn grant_permissions(&mut self, command: &Command) {
+        self.granted_permissions.insert(
+            command.id.clone(),
+            GrantedPermissions {
+                permissions: command.info.permissions.clone(),
+                files_hash: command.files_hash.clone(),
+            },
+        );
+    }
+}
+
 #[tauri::command]
 pub async fn get_settings(state: tauri::State<'_, Mutex<Settings>>) -> Result<Settings, ()> {
     let state = state.lock().await;
@@ -94,13 +166,24 @@ pub async fn set_wrap_lines(
     save_settings(app, state.deref())
 }
 
+#[tauri::command]
+pub async fn set_script_timeout(
+    app: AppHandle,
+    state: tauri::State<'_, Mutex<Settings>>,
+    script_timeout_seconds: u64,
+) -> Result<Settings, ()> {
+    let mut state = state.lock().await;
+    state.script_timeout_seconds = script_timeout_seconds.max(1);
+    save_settings(app, state.deref())
+}
+
 pub fn open_settings_window(app: &AppHandle) {
     let settings_window_result = WebviewWindowBuilder::new(
         app,
         "settings".to_string(),
         WebviewUrl::App("windows/settings.html".parse().unwrap()),
     )
-    .inner_size(400.0, 250.0)
+    .inner_size(400.0, 440.0)
     .resizable(false)
     .title("Snip Settings")
     .build();
@@ -1,12 +1,26 @@
+use crate::document::{open_file_from_dialog, revert_document_in_window};
+use crate::scripts::commands::reexecute_last_script_in_window;
+use serde::Serialize;
+#[cfg(not(target_os = "macos"))]
+use std::collections::BTreeMap;
+#[cfg(target_os = "macos")]
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use tauri::async_runtime::spawn;
 use tauri::menu::{
-    AboutMetadataBuilder, Menu, MenuEvent, MenuItemBuilder, Submenu, SubmenuBuilder,
+    AboutMetadataBuilder, Menu, MenuEvent, MenuItem, MenuItemBuilder, Submenu, SubmenuBuilder,
 };
 use tauri::{AppHandle, Emitter, Manager, WebviewWindow, Window, Wry};
 
 const MENU_ITEM_ID_SETTINGS: &str = "settings";
 const MENU_ITEM_ID_NEW_WINDOW: &str = "window_new";
+const MENU_ITEM_ID_FILE_OPEN: &str = "file_open";
+const MENU_ITEM_ID_FILE_SAVE: &str = "file_save";
+const MENU_ITEM_ID_FILE_SAVE_AS: &str = "file_save_as";
+const MENU_ITEM_ID_FILE_REVERT: &str = "file_revert";
 const MENU_ITEM_ID_SCRIPTS_OPEN_PICKER: &str = "scripts_open_picker";
 const MENU_ITEM_ID_SCRIPTS_REEXECUTE_LAST: &str = "scripts_reexecute_last";
+const MENU_ITEM_ID_SCRIPTS_TOGGLE_LOG: &str = "scripts_toggle_log";
 
 fn get_file_sub_menu(app: &AppHandle, in_settings: bool) -> Result<Submenu<Wry>, tauri::Error> {
     let mut builder = SubmenuBuilder::new(app, "File");
@@ -15,12 +29,39 @@ fn get_file_sub_menu(app: &AppHandle, in_settings: bool) -> Result<Submenu<Wry>,
             .id(MENU_ITEM_ID_NEW_WINDOW)
             .accelerator("CmdOrCtrl+N")
             .build(app)?;
-        builder = builder.item(&new_window_item).separator();
+        let open_item = MenuItemBuilder::new("Open...")
+            .id(MENU_ITEM_ID_FILE_OPEN)
+            .accelerator("CmdOrCtrl+O")
+            .build(app)?;
+        let save_item = MenuItemBuilder::new("Save")
+            .id(MENU_ITEM_ID_FILE_SAVE)
+            .accelerator("CmdOrCtrl+S")
+            .build(app)?;
+        let save_as_item = MenuItemBuilder::new("Save As...")
+            .id(MENU_ITEM_ID_FILE_SAVE_AS)
+            .accelerator("CmdOrCtrl+Shift+S")
+            .build(app)?;
+        let revert_item = MenuItemBuilder::new("Revert")
+            .id(MENU_ITEM_ID_FILE_REVERT)
+            .build(app)?;
+        builder = builder
+            .item(&new_window_item)
+            .item(&open_item)
+            .separator()
+            .item(&save_item)
+            .item(&save_as_item)
+            .item(&revert_item)
+            .separator();
     }
     builder.close_window().build()
 }
 
-fn build_menu(app: &AppHandle, in_settings: bool) -> Result<Menu<tauri::Wry>, tauri::Error> {
+/// Builds the menu, together with its "Re-execute Last Script" item when it has one.
+fn build_menu(
+    app: &AppHandle,
+    in_settings: bool,
+    can_reexecute_script: bool,
+) -> Result<(Menu<tauri::Wry>, Option<MenuItem<Wry>>), tauri::Error> {
     let settings = MenuItemBuilder::new("Settings...")
         .id(MENU_ITEM_ID_SETTINGS)
         .accelerator("CmdOrCtrl+,")
@@ -74,12 +115,17 @@ fn build_menu(app: &AppHandle, in_settings: bool) -> Result<Menu<tauri::Wry>, ta
         let reexecute_last_script_item = MenuItemBuilder::new("Re-execute Last Script")
             .id(MENU_ITEM_ID_SCRIPTS_REEXECUTE_LAST)
             .accelerator("CmdOrCtrl+Shift+B")
-            .enabled(false)
+            .enabled(can_reexecute_script)
+            .build(app)?;
+        let toggle_script_log_item = MenuItemBuilder::new("Show/Hide Script Output")
+            .id(MENU_ITEM_ID_SCRIPTS_TOGGLE_LOG)
             .build(app)?;
         let scripts_sub_menu = SubmenuBuilder::new(app, "Scripts")
             .item(&open_script_picker_item)
             .separator()
             .item(&reexecute_last_script_item)
+            .separator()
+            .item(&toggle_script_log_item)
             .build()?;
         app_menu.append_items(&[
             &app_sub_menu,
@@ -87,11 +133,17 @@ fn build_menu(app: &AppHandle, in_settings: bool) -> Result<Menu<tauri::Wry>, ta
             &edit_sub_menu,
             &scripts_sub_menu,
         ])?;
+        Ok((app_menu, Some(reexecute_last_script_item)))
     } else {
         app_menu.append_items(&[&app_sub_menu, &file_sub_menu])?;
+        Ok((app_menu, None))
     }
+}
 
-    Ok(app_menu)
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveDocumentEvent {
+    save_as: bool,
 }
 
 fn handle_menu_event(app: &AppHandle, window: &Window, event: MenuEvent) {
@@ -102,9 +154,44 @@ fn handle_menu_event(app: &AppHandle, window: &Window, event: MenuEvent) {
         MENU_ITEM_ID_SCRIPTS_OPEN_PICKER => {
             app.emit_to(window.label(), "open_picker", true).unwrap()
         }
+        MENU_ITEM_ID_SCRIPTS_TOGGLE_LOG => app
+            .emit_to(window.label(), "toggle_script_log", true)
+            .unwrap(),
+        MENU_ITEM_ID_SCRIPTS_REEXECUTE_LAST => {
+            spawn(reexecute_last_script_in_window(
+                app.clone(),
+                window.label().to_string(),
+            ));
+        }
         MENU_ITEM_ID_NEW_WINDOW => {
             app.emit("new_window", true).unwrap();
         }
+        MENU_ITEM_ID_FILE_OPEN => {
+            spawn(open_file_from_dialog(
+                app.clone(),
+                window.label().to_string(),
+            ));
+        }
+        MENU_ITEM_ID_FILE_SAVE => app
+            .emit_to(
+                window.label(),
+                "save_document",
+                SaveDocumentEvent { save_as: false },
+            )
+            .unwrap(),
+        MENU_ITEM_ID_FILE_SAVE_AS => app
+            .emit_to(
+                window.label(),
+                "save_document",
+                SaveDocumentEvent { save_as: true },
+            )
+            .unwrap(),
+        MENU_ITEM_ID_FILE_REVERT => {
+            spawn(revert_document_in_window(
+                app.clone(),
+                window.label().to_string(),
+            ));
+        }
         _ => {}
     }
 }
@@ -116,7 +203,7 @@ pub fn initialize_global_handlers(app: &AppHandle) {
         if let Some(focused_window) = focused_window {
             handle_menu_event(app, &focused_window, event);
         } else {
-            println!("Warning: No window in focus, ignoring menu event");
+            log::warn!("No window in focus, ignoring menu event");
         }
     });
 }
@@ -124,6 +211,11 @@ pub fn initialize_global_handlers(app: &AppHandle) {
 #[cfg(target_os = "windows")]
 pub fn initialize_global_handlers(_app: &AppHandle) {}
 
+/// The "Re-execute Last Script" item of the menu of each window, so it can be enabled without
+/// rebuilding the menu.
+#[cfg(not(target_os = "macos"))]
+static REEXECUTE_MENU_ITEMS: Mutex<BTreeMap<String, MenuItem<Wry>>> = Mutex::new(BTreeMap::new());
+
 #[cfg(not(target_os = "macos"))]
 pub fn on_new_window(window: &WebviewWindow) {
     if window.label() == "settings" {
@@ -136,18 +228,79 @@ pub fn on_new_window(window: &WebviewWindow) {
         });
     }
 
-    let menu = build_menu(window.app_handle(), false).unwrap();
+    let (menu, reexecute_last_script_item) = build_menu(window.app_handle(), false, false).unwrap();
     window.set_menu(menu).unwrap();
+    if let Some(item) = reexecute_last_script_item {
+        REEXECUTE_MENU_ITEMS
+            .lock()
+            .unwrap()
+            .insert(window.label().to_string(), item);
+    }
 }
 
 #[cfg(target_os = "macos")]
 pub fn on_new_window(_window: &WebviewWindow) {}
 
+/// Enables the "Re-execute Last Script" item once a script has completed in the window.
+#[cfg(not(target_os = "macos"))]
+pub fn on_script_completed(window: &WebviewWindow) {
+    if let Some(item) = REEXECUTE_MENU_ITEMS.lock().unwrap().get(window.label()) {
+        item.set_enabled(true).expect("Could not enable menu item");
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn on_window_destroyed(window: &Window) {
+    REEXECUTE_MENU_ITEMS.lock().unwrap().remove(window.label());
+}
+
+/// The windows in which a script has completed. The focus handler runs on the main thread, so it
+/// reads these instead of waiting for the `Windows` lock, which may be held by a task that waits
+/// for the main thread itself.
+#[cfg(target_os = "macos")]
+static SCRIPT_COMPLETED_WINDOWS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The "Re-execute Last Script" item of the current app menu, if it has one.
+#[cfg(target_os = "macos")]
+static REEXECUTE_MENU_ITEM: Mutex<Option<MenuItem<Wry>>> = Mutex::new(None);
+
+/// Enables the "Re-execute Last Script" item once a script has completed in the window. The app
+/// menu is shared by all windows, so it only changes when the window still has focus.
+#[cfg(target_os = "macos")]
+pub fn on_script_completed(window: &WebviewWindow) {
+    SCRIPT_COMPLETED_WINDOWS
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string());
+    if window.is_focused().unwrap_or(false) {
+        if let Some(item) = REEXECUTE_MENU_ITEM.lock().unwrap().as_ref() {
+            item.set_enabled(true).expect("Could not enable menu item");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn on_window_destroyed(window: &Window) {
+    SCRIPT_COMPLETED_WINDOWS
+        .lock()
+        .unwrap()
+        .remove(window.label());
+}
+
 #[cfg(target_os = "macos")]
 pub fn on_window_focus_change(window: &Window) {
+    let can_reexecute_script = SCRIPT_COMPLETED_WINDOWS
+        .lock()
+        .unwrap()
+        .contains(window.label());
     (|| -> Result<(), tauri::Error> {
-        let menu = build_menu(window.app_handle(), window.label() == "settings")?;
+        let (menu, reexecute_last_script_item) = build_menu(
+            window.app_handle(),
+            window.label() == "settings",
+            can_reexecute_script,
+        )?;
         menu.set_as_app_menu()?;
+        *REEXECUTE_MENU_ITEM.lock().unwrap() = reexecute_last_script_item;
         Ok(())
     })()
     .expect("Could not replace menu");
@@ -1,10 +1,13 @@
 pub mod menu;
 
+use crate::document::{DocumentState, OpenedDocument};
 use crate::scripts::commands::{handle_script_task, ScriptTask, WindowScriptState};
+use crate::scripts::runtime::ScriptRuntimePool;
 use std::collections::HashMap;
 use std::thread;
 use std::thread::JoinHandle;
-use tauri::{AppHandle, Error, WebviewUrl, WebviewWindowBuilder};
+use tauri::async_runtime::Mutex;
+use tauri::{AppHandle, Error, Manager, State, WebviewUrl, WebviewWindowBuilder};
 use tokio::runtime;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -18,6 +21,7 @@ pub struct WindowState {
     thread_join_handle: JoinHandle<()>,
     task_sender: Sender<WindowTask>,
     script_state: WindowScriptState,
+    document: DocumentState,
 }
 
 pub struct Windows {
@@ -31,11 +35,18 @@ fn window_task_listener(mut task_receiver: Receiver<WindowTask>) {
         .build()
         .expect("Could not create tokio runtime");
     rt.block_on(async {
+        let mut runtime_pool = ScriptRuntimePool::new();
         loop {
             let task = task_receiver.recv().await;
             if let Some(task) = task {
                 match task {
-                    WindowTask::Script(script_event) => handle_script_task(script_event).await,
+                    WindowTask::Script(script_event) => {
+                        handle_script_task(script_event, &mut runtime_pool).await;
+                        // A window that ran a script is likely to run another one, so prepare
+                        // the runtime for it while waiting. Windows that never run a script do
+                        // not get a runtime at all.
+                        runtime_pool.refill();
+                    }
                     WindowTask::Close => break,
                 }
             } else {
@@ -45,6 +56,28 @@ fn window_task_listener(mut task_receiver: Receiver<WindowTask>) {
     })
 }
 
+struct NewWindow {
+    label: String,
+    title: String,
+}
+
+impl NewWindow {
+    fn build(self, app_handle: &AppHandle) -> Result<(), Error> {
+        let window = WebviewWindowBuilder::new(
+            app_handle,
+            self.label,
+            WebviewUrl::App("windows/index.html".parse().unwrap()),
+        )
+        .min_inner_size(800.0, 600.0)
+        .title(self.title)
+        .build()?;
+
+        menu::on_new_window(&window);
+
+        Ok(())
+    }
+}
+
 impl Windows {
     pub fn new() -> Self {
         Self {
@@ -53,37 +86,42 @@ impl Windows {
         }
     }
 
-    pub fn create_window(&mut self, app_handle: &AppHandle) -> Result<(), Error> {
+    /// Creates a window while no other task can hold the `Windows` lock, such as during setup.
+    pub fn create_window_with_document(
+        &mut self,
+        app_handle: &AppHandle,
+        document: Option<OpenedDocument>,
+    ) -> Result<(), Error> {
+        self.add_window(document)?.build(app_handle)
+    }
+
+    /// Registers the state of a new window. The window itself is built by [`NewWindow::build`],
+    /// which must be called after the `Windows` lock is released, as building a window waits for
+    /// the main thread.
+    fn add_window(&mut self, document: Option<OpenedDocument>) -> Result<NewWindow, Error> {
         self.last_window_id += 1;
-        let id = format!("main_{}", self.last_window_id);
+        let label = format!("main_{}", self.last_window_id);
 
         let (task_sender, task_receiver) = channel(8);
 
         let thread_join_handle = thread::Builder::new()
-            .name(format!("{}_runner", id))
+            .name(format!("{}_runner", label))
             .spawn(move || window_task_listener(task_receiver))?;
 
+        let document = DocumentState::new(self.last_window_id, document);
+        let title = document.title();
+
         self.window_states.insert(
-            id.clone(),
+            label.clone(),
             WindowState {
                 thread_join_handle,
                 task_sender,
                 script_state: Default::default(),
+                document,
             },
         );
 
-        let window = WebviewWindowBuilder::new(
-            app_handle,
-            id,
-            WebviewUrl::App("windows/index.html".parse().unwrap()),
-        )
-        .min_inner_size(800.0, 600.0)
-        .title(format!("Snip - Untitled {}", self.last_window_id))
-        .build()?;
-
-        menu::on_new_window(&window);
-
-        Ok(())
+        Ok(NewWindow { label, title })
     }
 
     pub async fn queue_task(
@@ -109,6 +147,12 @@ impl Windows {
         }
     }
 
+    pub fn get_document_state(&mut self, window_id: &str) -> Option<&mut DocumentState> {
+        self.window_states
+            .get_mut(window_id)
+            .map(|window_state| &mut window_state.document)
+    }
+
     pub fn destroy_window(&mut self, window_id: &str) {
         if let Some(window_state) = self.window_states.remove(window_id) {
             window_state
@@ -119,7 +163,29 @@ impl Windows {
         }
     }
 
+    /// Removes the state of a window that could not be built. Dropping its task sender stops the
+    /// task thread, without waiting for it like [`Windows::destroy_window`] does.
+    fn forget_window(&mut self, window_id: &str) {
+        self.window_states.remove(window_id);
+    }
+
     pub fn has_open(&self) -> bool {
         !self.window_states.is_empty()
     }
 }
+
+/// Opens a new window from a task that runs while other tasks may hold the `Windows` lock. The
+/// lock is only held to register the window, not while the window is built on the main thread.
+pub async fn open_window(
+    app_handle: &AppHandle,
+    document: Option<OpenedDocument>,
+) -> Result<(), Error> {
+    let windows: State<'_, Mutex<Windows>> = app_handle.state();
+    let new_window = windows.lock().await.add_window(document)?;
+    let label = new_window.label.clone();
+    let result = new_window.build(app_handle);
+    if result.is_err() {
+        windows.lock().await.forget_window(&label);
+    }
+    result
+}
@@ -1,6 +1,10 @@
 import { platform } from '@tauri-apps/plugin-os'
 import { useMemo } from 'react'
 import cn from '../modules/classnames.ts'
+import {
+  ScriptError,
+  ScriptStatus,
+} from '../modules/useScriptCommandRunner.ts'
 
 const currentPlatform = platform()
 const scriptStatusShortcutText = `Press ${currentPlatform === 'macos' ? '⌘' : 'Ctrl'}+B to start a command`
@@ -8,43 +12,98 @@ const scriptStatusShortcutText = `Press ${currentPlatform === 'macos' ? '⌘' :
 export interface CommandStatusProps {
   pickerOpen?: boolean
   running: boolean
-  error?: string | undefined
+  cancelled?: boolean
+  error?: ScriptError | undefined
+  status?: ScriptStatus | undefined
+}
+
+const statusLevelClassNames: Record<ScriptStatus['level'], string> = {
+  info: '',
+  success: 'bg-green-700 text-white',
+  warning: 'bg-yellow-400 text-black',
+  error: 'bg-red-800 text-white',
+}
+
+function errorLocation(error: ScriptError) {
+  if (!error.file) {
+    return undefined
+  }
+  const fileName = error.file.split(/[\\/]/).pop()
+  return error.line !== undefined && error.line !== null
+    ? `${fileName}:${error.line}`
+    : fileName
 }
 
 export default function CommandStatus({
   pickerOpen,
   running,
+  cancelled,
   error,
+  status,
 }: CommandStatusProps) {
+  // A running command keeps its status visible, an error or a cancelled run
+  // replaces it
+  const shownStatus = !pickerOpen && !error && !cancelled ? status : undefined
+
   const statusText = useMemo(() => {
     if (pickerOpen) {
       return 'Select a command'
     }
     if (error) {
-      if (error.startsWith('Error: ')) {
-        // The default javascript Error.toString() already prepends our text with 'Error: '
-        return error
-      }
-      return `Error: ${error}`
+      // The default javascript Error.toString() already prepends our text with 'Error: '
+      const message = /^(Uncaught )?Error: /.test(error.message)
+        ? error.message
+        : `Error: ${error.message}`
+      const location = errorLocation(error)
+      return location ? `${message} (${location})` : message
     }
     if (running) {
-      return 'Command is running...'
+      return status
+        ? `${status.message} (Press Esc to cancel)`
+        : 'Command is running... Press Esc to cancel'
+    }
+    if (cancelled) {
+      return 'Command was cancelled'
+    }
+    if (status) {
+      return status.message
     }
     return scriptStatusShortcutText
-  }, [pickerOpen, running, error])
+  }, [pickerOpen, running, cancelled, error, status])
 
   const classNames = useMemo(
     () =>
       cn(
         'flex-1 bg-theme-300 rounded-xl m-1 p-1 px-3 justify-center max-w-96 text-theme-700 text-center truncate',
         error && !pickerOpen && 'bg-red-800 text-white',
+        shownStatus && statusLevelClassNames[shownStatus.level],
       ),
-    [error, pickerOpen],
+    [error, pickerOpen, shownStatus],
   )
 
+  // The full stack is available when hovering the error, and long status
+  // messages can be read in full
+  const title = useMemo(() => {
+    if (shownStatus) {
+      return shownStatus.message
+    }
+    if (!error || pickerOpen) {
+      return undefined
+    }
+    return [
+      error.message,
+      ...error.stack.map(
+        (frame) =>
+          `    at ${frame.functionName ?? '<anonymous>'} (${frame.file}:${frame.line}:${frame.column})`,
+      ),
+    ].join('\n')
+  }, [error, pickerOpen, shownStatus])
+
   return (
     <div className="flex flex-row justify-center">
-      <span className={classNames}>{statusText}</span>
+      <span className={classNames} title={title}>
+        {statusText}
+      </span>
     </div>
   )
 }
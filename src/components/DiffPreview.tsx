@@ -0,0 +1,109 @@
+import { Fragment } from 'react'
+import cn from '../modules/classnames.ts'
+import { DiffLine, ScriptPreview } from '../modules/useScriptCommandRunner.ts'
+
+export interface DiffPreviewProps {
+  preview: ScriptPreview
+  onAccept: () => void
+  onReject: () => void
+}
+
+const linePrefixes: Record<DiffLine['kind'], string> = {
+  unchanged: ' ',
+  deleted: '-',
+  inserted: '+',
+}
+
+function DiffLineText({ line }: { line: DiffLine }) {
+  if (line.highlights.length === 0) {
+    return <>{line.text}</>
+  }
+  const parts = []
+  let offset = 0
+  for (const [start, end] of line.highlights) {
+    parts.push(line.text.slice(offset, start))
+    parts.push(
+      <mark
+        key={start}
+        className={cn(
+          'text-inherit',
+          line.kind === 'deleted' ? 'bg-red-300' : 'bg-green-300',
+        )}
+      >
+        {line.text.slice(start, end)}
+      </mark>,
+    )
+    offset = end
+  }
+  parts.push(line.text.slice(offset))
+  return <>{parts}</>
+}
+
+// Shows the changes of a script that ran in preview mode, so the user can
+// decide whether they should be applied
+export default function DiffPreview({
+  preview,
+  onAccept,
+  onReject,
+}: DiffPreviewProps) {
+  const { hunks, deletedLines, insertedLines } = preview.diff
+  return (
+    <div
+      className="absolute flex size-full justify-center"
+      onKeyUp={(e) => {
+        if (e.key === 'Escape') {
+          onReject()
+        }
+      }}
+    >
+      <div className="mx-8 mt-16 flex h-fit max-h-[80%] w-full max-w-4xl flex-col rounded-xl border-2 border-theme-600 bg-theme-50 p-3 text-theme-950">
+        <div className="mb-2 font-bold">
+          {hunks.length === 0
+            ? 'The script did not change the document'
+            : `${insertedLines} lines added, ${deletedLines} lines removed`}
+        </div>
+        <div className="overflow-auto rounded-lg border-2 border-theme-600 font-mono text-sm">
+          {hunks.map((hunk, hunkIndex) => (
+            <Fragment key={hunkIndex}>
+              <div className="bg-theme-200 px-2 text-theme-700">
+                {`@@ -${hunk.oldStart},${hunk.oldCount} +${hunk.newStart},${hunk.newCount} @@`}
+              </div>
+              {hunk.lines.map((line, lineIndex) => (
+                <div
+                  key={lineIndex}
+                  className={cn(
+                    'whitespace-pre px-2',
+                    line.kind === 'deleted' && 'bg-red-100',
+                    line.kind === 'inserted' && 'bg-green-100',
+                  )}
+                >
+                  {linePrefixes[line.kind]}
+                  <DiffLineText line={line} />
+                </div>
+              ))}
+            </Fragment>
+          ))}
+        </div>
+        <div className="mt-2 flex flex-row justify-end gap-2">
+          <button
+            type="button"
+            onClick={onReject}
+            autoFocus={hunks.length === 0}
+          >
+            {hunks.length === 0 ? 'Close' : 'Discard'}
+          </button>
+          {hunks.length > 0 && (
+            <button
+              type="button"
+              className="font-bold"
+              onClick={onAccept}
+              autoFocus
+            >
+              Apply
+            </button>
+          )}
+        </div>
+      </div>
+    </div>
+  )
+}
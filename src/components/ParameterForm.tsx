@@ -0,0 +1,175 @@
+import { FormEvent, MouseEventHandler, useMemo, useState } from 'react'
+import {
+  Command,
+  CommandParameter,
+  initialParameterValues,
+  ParameterValue,
+  ParameterValues,
+} from '../modules/commandParameters.ts'
+
+export interface ParameterFormProps {
+  command: Command
+  onRun: (parameters: ParameterValues) => void
+  onClose: () => void
+}
+
+const inputClassName =
+  'w-full rounded-lg border-2 border-theme-600 bg-theme-50 p-1 px-2 text-theme-950 focus:border-theme-800 focus:outline-none'
+
+const stopPropagationClickHandler: MouseEventHandler<HTMLFormElement> = (e) => {
+  e.stopPropagation()
+}
+
+function ParameterInput({
+  parameter,
+  value,
+  onChange,
+  autoFocus,
+}: {
+  parameter: CommandParameter
+  value: ParameterValue | undefined
+  onChange: (value: ParameterValue | undefined) => void
+  autoFocus: boolean
+}) {
+  switch (parameter.type) {
+    case 'boolean':
+      return (
+        <input
+          type="checkbox"
+          checked={value === true}
+          onChange={(e) => onChange(e.target.checked)}
+          autoFocus={autoFocus}
+        />
+      )
+    case 'enum':
+      return (
+        <select
+          className={inputClassName}
+          value={value as string}
+          onChange={(e) => onChange(e.target.value)}
+          autoFocus={autoFocus}
+        >
+          {parameter.options.map((option) => (
+            <option key={option} value={option}>
+              {option}
+            </option>
+          ))}
+        </select>
+      )
+    case 'number':
+      return (
+        <input
+          type="number"
+          className={inputClassName}
+          value={value === undefined ? '' : String(value)}
+          min={parameter.min}
+          max={parameter.max}
+          step="any"
+          onChange={(e) =>
+            onChange(
+              e.target.value === '' ? undefined : Number(e.target.value),
+            )
+          }
+          autoFocus={autoFocus}
+        />
+      )
+    case 'string':
+      return (
+        <input
+          type="text"
+          className={inputClassName}
+          value={(value as string | undefined) ?? ''}
+          spellCheck={false}
+          onChange={(e) => onChange(e.target.value)}
+          autoFocus={autoFocus}
+        />
+      )
+  }
+}
+
+// Asks for the parameters of a command before it runs
+export default function ParameterForm({
+  command,
+  onRun,
+  onClose,
+}: ParameterFormProps) {
+  const [values, setValues] = useState<ParameterValues>(() =>
+    initialParameterValues(command.parameters),
+  )
+
+  const missingValue = useMemo(
+    () =>
+      command.parameters.some(
+        (parameter) =>
+          values[parameter.name] === undefined ||
+          (parameter.type === 'number' &&
+            isNaN(values[parameter.name] as number)),
+      ),
+    [command.parameters, values],
+  )
+
+  const onSubmit = (e: FormEvent) => {
+    e.preventDefault()
+    if (missingValue) {
+      return
+    }
+    onRun(values)
+    onClose()
+  }
+
+  return (
+    <div
+      className="absolute flex size-full justify-center"
+      onClick={() => onClose()}
+    >
+      <form
+        className="mt-32 h-fit w-96 rounded-xl border-2 border-theme-600 bg-theme-50 p-3 text-theme-950"
+        onClick={stopPropagationClickHandler}
+        onSubmit={onSubmit}
+        onKeyUp={(e) => {
+          if (e.key === 'Escape') {
+            onClose()
+          }
+        }}
+      >
+        <div className="mb-2 text-lg font-bold">{command.title}</div>
+        {command.parameters.map((parameter, index) => (
+          <label key={parameter.name} className="mb-2 block">
+            <span className="block text-sm font-medium">
+              {parameter.title}
+            </span>
+            <ParameterInput
+              parameter={parameter}
+              value={values[parameter.name]}
+              onChange={(value) =>
+                setValues((previousValues) => {
+                  const newValues = { ...previousValues }
+                  if (value === undefined) {
+                    delete newValues[parameter.name]
+                  } else {
+                    newValues[parameter.name] = value
+                  }
+                  return newValues
+                })
+              }
+              autoFocus={index === 0}
+            />
+            {parameter.description && (
+              <span className="block text-sm italic text-theme-700">
+                {parameter.description}
+              </span>
+            )}
+          </label>
+        ))}
+        <div className="flex flex-row justify-end gap-2">
+          <button type="button" onClick={onClose}>
+            Cancel
+          </button>
+          <button type="submit" className="font-bold" disabled={missingValue}>
+            Run
+          </button>
+        </div>
+      </form>
+    </div>
+  )
+}
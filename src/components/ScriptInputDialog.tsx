@@ -0,0 +1,139 @@
+import { useState } from 'react'
+import cn from '../modules/classnames.ts'
+import { UserInputRequest } from '../modules/useScriptCommandRunner.ts'
+
+export interface ScriptInputDialogProps {
+  request: UserInputRequest
+  onAnswer: (
+    request: UserInputRequest,
+    answer: string | boolean | number,
+  ) => void
+  // Cancelling stops the script that asked
+  onCancel: () => void
+}
+
+const inputClassName =
+  'w-full rounded-lg border-2 border-theme-600 bg-theme-50 p-1 px-2 text-theme-950 focus:border-theme-800 focus:outline-none'
+
+function PromptInput({
+  defaultValue,
+  onSubmit,
+}: {
+  defaultValue?: string
+  onSubmit: (answer: string) => void
+}) {
+  const [value, setValue] = useState(defaultValue ?? '')
+  return (
+    <form
+      onSubmit={(e) => {
+        e.preventDefault()
+        onSubmit(value)
+      }}
+    >
+      <input
+        type="text"
+        className={inputClassName}
+        value={value}
+        spellCheck={false}
+        onChange={(e) => setValue(e.target.value)}
+        autoFocus
+      />
+    </form>
+  )
+}
+
+function OptionList({
+  options,
+  onPick,
+}: {
+  options: string[]
+  onPick: (index: number) => void
+}) {
+  const [selectedIndex, setSelectedIndex] = useState(0)
+  return (
+    <div
+      className="max-h-64 snap-y overflow-y-auto rounded-lg border-2 border-theme-600 focus:border-theme-800 focus:outline-none"
+      tabIndex={0}
+      autoFocus
+      onKeyDown={(e) => {
+        switch (e.key) {
+          case 'Enter':
+            onPick(selectedIndex)
+            break
+          case 'ArrowUp':
+            setSelectedIndex((prevIndex) => Math.max(prevIndex - 1, 0))
+            break
+          case 'ArrowDown':
+            setSelectedIndex((prevIndex) =>
+              Math.min(prevIndex + 1, options.length - 1),
+            )
+            break
+          default:
+            return
+        }
+        e.preventDefault()
+      }}
+    >
+      {options.map((option, index) => (
+        <a
+          key={index}
+          className={cn(
+            'block cursor-pointer px-3 py-1 hover:bg-theme-200 snap-start',
+            index === selectedIndex && 'bg-theme-300',
+          )}
+          onClick={() => onPick(index)}
+        >
+          {option}
+        </a>
+      ))}
+    </div>
+  )
+}
+
+// Shows a question from a running script. Escape cancels the script, like it
+// does for any running script.
+export default function ScriptInputDialog({
+  request,
+  onAnswer,
+  onCancel,
+}: ScriptInputDialogProps) {
+  return (
+    <div className="absolute flex size-full justify-center">
+      <div className="mt-32 h-fit w-96 rounded-xl border-2 border-theme-600 bg-theme-50 p-3 text-theme-950">
+        <div className="mb-2 whitespace-pre-wrap">{request.data.message}</div>
+        {request.event === 'prompt' && (
+          <PromptInput
+            defaultValue={request.data.defaultValue}
+            onSubmit={(answer) => onAnswer(request, answer)}
+          />
+        )}
+        {request.event === 'pickFromList' && (
+          <OptionList
+            options={request.data.options}
+            onPick={(index) => onAnswer(request, index)}
+          />
+        )}
+        <div className="mt-2 flex flex-row justify-end gap-2">
+          <button type="button" onClick={onCancel}>
+            Cancel
+          </button>
+          {request.event === 'confirm' && (
+            <>
+              <button type="button" onClick={() => onAnswer(request, false)}>
+                No
+              </button>
+              <button
+                type="button"
+                className="font-bold"
+                onClick={() => onAnswer(request, true)}
+                autoFocus
+              >
+                Yes
+              </button>
+            </>
+          )}
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,93 @@
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import cn from '../modules/classnames.ts'
+
+type ScriptLogLevel = 'debug' | 'info' | 'warn' | 'error'
+
+interface ScriptLogEntry {
+  level: ScriptLogLevel
+  message: string
+  timestamp: number
+}
+
+interface ScriptRunLog {
+  commandId: string
+  commandTitle: string
+  startedAt: number
+  entries: ScriptLogEntry[]
+  droppedEntries: number
+}
+
+export interface ScriptLogPanelProps {
+  // Changes whenever new output is available
+  logVersion: number
+  onClose: () => void
+}
+
+const levelClassNames: Record<ScriptLogLevel, string> = {
+  debug: 'text-theme-500',
+  info: 'text-theme-800',
+  warn: 'text-yellow-700',
+  error: 'text-red-700',
+}
+
+function formatTime(timestamp: number) {
+  return new Date(timestamp).toLocaleTimeString()
+}
+
+export default function ScriptLogPanel({
+  logVersion,
+  onClose,
+}: ScriptLogPanelProps) {
+  const [runs, setRuns] = useState<ScriptRunLog[]>([])
+
+  useEffect(() => {
+    invoke<ScriptRunLog[]>('get_script_logs')
+      .then((runs) => setRuns(runs))
+      .catch((e) => {
+        console.warn('Could not load script logs', e)
+      })
+  }, [logVersion])
+
+  return (
+    <div className="flex max-h-64 flex-col border-t-2 border-theme-600 bg-theme-50">
+      <div className="flex flex-row justify-between px-3 py-1 text-theme-700">
+        <span className="font-bold">Script output</span>
+        <button type="button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+      <div className="overflow-y-auto px-3 pb-2 font-mono text-sm">
+        {runs.length === 0 && (
+          <div className="text-theme-600">No scripts have run yet</div>
+        )}
+        {[...runs].reverse().map((run) => (
+          <div key={`${run.commandId}-${run.startedAt}`} className="mb-2">
+            <div className="text-theme-700">
+              {formatTime(run.startedAt)} {run.commandTitle}
+            </div>
+            {run.entries.length === 0 && (
+              <div className="pl-4 text-theme-500">No output</div>
+            )}
+            {run.entries.map((entry, index) => (
+              <div
+                key={index}
+                className={cn(
+                  'whitespace-pre-wrap pl-4',
+                  levelClassNames[entry.level],
+                )}
+              >
+                {entry.message}
+              </div>
+            ))}
+            {run.droppedEntries > 0 && (
+              <div className="pl-4 text-theme-500">
+                {run.droppedEntries} more lines were not kept
+              </div>
+            )}
+          </div>
+        ))}
+      </div>
+    </div>
+  )
+}
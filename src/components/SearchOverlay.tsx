@@ -1,16 +1,19 @@
-import { MouseEventHandler, useState } from 'react'
+import {
+  MouseEventHandler,
+  useCallback,
+  useEffect,
+  useRef,
+  useState,
+} from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import cn from '../modules/classnames.ts'
+import { Command } from '../modules/commandParameters.ts'
 
 export interface SearchOverlayProps {
   onClose: () => void
-  onRunCommand: (scriptId: string) => void | Promise<void>
-}
-
-interface Command {
-  id: string
-  title: string
-  description: string
+  // Preview runs show the changes of the command before applying them
+  onSelectCommand: (command: Command, preview: boolean) => void | Promise<void>
 }
 
 interface CommandSearchResult {
@@ -25,12 +28,48 @@ const stopPropagationClickHandler: MouseEventHandler<HTMLDivElement> = (e) => {
 
 export default function SearchOverlay({
   onClose,
-  onRunCommand,
+  onSelectCommand,
 }: SearchOverlayProps) {
   const [matchingScripts, setMatchingScripts] = useState<CommandSearchResult[]>(
     [],
   )
   const [selectedIndex, setSelectedIndex] = useState<number>(0)
+  const searchTermRef = useRef('')
+
+  const search = useCallback((searchTerm: string) => {
+    searchTermRef.current = searchTerm
+    if (!searchTerm) {
+      setMatchingScripts([])
+      return
+    }
+    invoke<CommandSearchResult[]>('get_script_commands', { searchTerm })
+      .then((scripts) => {
+        // Ignore results for a search term that has changed in the meantime
+        if (searchTermRef.current !== searchTerm) {
+          return
+        }
+        setMatchingScripts(scripts)
+        setSelectedIndex((prevIndex) =>
+          Math.min(prevIndex, Math.max(scripts.length - 1, 0)),
+        )
+      })
+      .catch((e) => {
+        console.warn('Error while searching for scripts', e)
+        setMatchingScripts([])
+      })
+  }, [])
+
+  useEffect(() => {
+    // Scripts are reloaded when they change on disk, so search them again
+    let unlistenFn: UnlistenFn | undefined = undefined
+    void (async () => {
+      unlistenFn = await listen('scripts_updated', () => {
+        search(searchTermRef.current)
+      })
+    })()
+
+    return () => unlistenFn?.()
+  }, [search])
 
   return (
     <div
@@ -50,7 +89,10 @@ export default function SearchOverlay({
                 break
               case 'Enter':
                 if (matchingScripts.length > 0) {
-                  void onRunCommand(matchingScripts[selectedIndex].command.id)
+                  void onSelectCommand(
+                    matchingScripts[selectedIndex].command,
+                    e.shiftKey,
+                  )
                   onClose()
                 }
                 break
@@ -66,20 +108,8 @@ export default function SearchOverlay({
             }
           }}
           onChange={(e) => {
-            const searchTerm = e.target.value
-            if (!searchTerm) {
-              setMatchingScripts([])
-              return
-            }
-            invoke('get_script_commands', { searchTerm: e.target.value })
-              .then((scripts) => {
-                setMatchingScripts(scripts as CommandSearchResult[])
-                setSelectedIndex(0)
-              })
-              .catch((e) => {
-                console.warn('Error while searching for scripts', e)
-                setMatchingScripts([])
-              })
+            setSelectedIndex(0)
+            search(e.target.value)
           }}
           autoFocus
         />
@@ -96,8 +126,8 @@ export default function SearchOverlay({
                       !isLast && 'border-b-[1px] border-theme-600',
                       index === selectedIndex && 'bg-theme-300',
                     )}
-                    onClick={() => {
-                      void onRunCommand(searchResult.command.id)
+                    onClick={(e) => {
+                      void onSelectCommand(searchResult.command, e.shiftKey)
                       onClose()
                     }}
                   >
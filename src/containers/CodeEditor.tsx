@@ -8,7 +8,7 @@ import CodeMirror, {
 } from '@uiw/react-codemirror'
 import { defaultKeymap } from '@codemirror/commands'
 import { langs } from '@uiw/codemirror-extensions-langs'
-import { useEffect, useMemo, useState } from 'react'
+import { useCallback, useEffect, useMemo, useState } from 'react'
 import useTheme from '../modules/useTheme.tsx'
 import {
   LanguageKey,
@@ -20,6 +20,14 @@ import useScriptCommandSelector from '../modules/useScriptCommandSelector.ts'
 import SearchOverlay from '../components/SearchOverlay.tsx'
 import useScriptCommandRunner from '../modules/useScriptCommandRunner.ts'
 import CommandStatus from '../components/CommandStatus.tsx'
+import useDocument from '../modules/useDocument.ts'
+import useScriptLogPanel from '../modules/useScriptLogPanel.ts'
+import ScriptLogPanel from '../components/ScriptLogPanel.tsx'
+import ParameterForm from '../components/ParameterForm.tsx'
+import ScriptInputDialog from '../components/ScriptInputDialog.tsx'
+import DiffPreview from '../components/DiffPreview.tsx'
+import { Command } from '../modules/commandParameters.ts'
+import { scriptAnnotations } from '../modules/scriptAnnotations.ts'
 
 function CodeEditor() {
   const settings = useSettings()
@@ -29,14 +37,18 @@ function CodeEditor() {
     settings.preferred_language,
   )
 
+  const [editorRef, setEditorRef] = useState<ReactCodeMirrorRef | null>(null)
+  const { onDocumentUpdate } = useDocument(editorRef)
+
   const onUpdateHandler = useMemo(
     () => (update: ViewUpdate) => {
       const head = update.state.selection.main.head
       const cursor = update.state.doc.lineAt(head)
       setCursorPosLine(cursor.number)
       setCursorPosCh(head - cursor.from)
+      onDocumentUpdate(update)
     },
-    [setCursorPosLine, setCursorPosCh],
+    [setCursorPosLine, setCursorPosCh, onDocumentUpdate],
   )
 
   const basicSetup = useMemo<BasicSetupOptions>(
@@ -50,17 +62,42 @@ function CodeEditor() {
   const { isOpen: commandPickerIsOpen, close: closeScriptSelector } =
     useScriptCommandSelector()
 
-  const [editorRef, setEditorRef] = useState<ReactCodeMirrorRef | null>(null)
   const {
     isRunning: commandIsRunning,
+    wasCancelled: commandWasCancelled,
     error: commandRunError,
+    status: commandStatus,
+    logVersion,
+    userInputRequest,
+    answerUserInputRequest,
+    preview,
+    acceptPreview,
+    rejectPreview,
     triggerCommand,
-  } = useScriptCommandRunner(editorRef)
+    cancelCommand,
+  } = useScriptCommandRunner(editorRef, currentLanguage, setCurrentLanguage)
+  const { isOpen: logPanelIsOpen, close: closeLogPanel } = useScriptLogPanel()
+  // A command with parameters waits for the user to fill them in before it runs
+  const [parameterCommand, setParameterCommand] = useState<{
+    command: Command
+    preview: boolean
+  }>()
+  const onSelectCommand = useCallback(
+    (command: Command, preview: boolean) => {
+      if (command.parameters.length > 0) {
+        setParameterCommand({ command, preview })
+      } else {
+        void triggerCommand(command.id, undefined, preview)
+      }
+    },
+    [triggerCommand],
+  )
 
   const extensions = useMemo(() => {
     const extensions: Extension[] = [
       keymap.of([...defaultKeymap]),
       EditorView.editable.of(!commandIsRunning),
+      scriptAnnotations,
     ]
     if (settings.wrap_lines) {
       extensions.push(EditorView.lineWrapping)
@@ -75,10 +112,10 @@ function CodeEditor() {
 
   const sortedLanguages = useSortedLanguages()
   useEffect(() => {
-    if (!commandPickerIsOpen) {
+    if (!commandPickerIsOpen && !parameterCommand && !preview) {
       editorRef?.view?.focus?.()
     }
-  }, [commandPickerIsOpen])
+  }, [commandPickerIsOpen, parameterCommand, preview])
   useEffect(() => {
     if (!commandIsRunning) {
       editorRef?.view?.focus?.()
@@ -86,7 +123,7 @@ function CodeEditor() {
   }, [commandIsRunning])
 
   return (
-    <div className="grid size-full grid-rows-[1fr_auto]">
+    <div className="grid size-full grid-rows-[1fr_auto_auto]">
       <div className="h-full overflow-auto overscroll-contain">
         <CodeMirror
           onUpdate={onUpdateHandler}
@@ -100,6 +137,9 @@ function CodeEditor() {
           readOnly={commandIsRunning}
         />
       </div>
+      {logPanelIsOpen && (
+        <ScriptLogPanel logVersion={logVersion} onClose={closeLogPanel} />
+      )}
       <div
         className="grid grid-cols-3"
         style={{
@@ -122,7 +162,9 @@ function CodeEditor() {
         </div>
         <CommandStatus
           running={commandIsRunning}
+          cancelled={commandWasCancelled}
           error={commandRunError}
+          status={commandStatus}
           pickerOpen={commandPickerIsOpen}
         />
         <div className="m-0 p-2 text-right text-theme-700">
@@ -132,7 +174,35 @@ function CodeEditor() {
       {commandPickerIsOpen && (
         <SearchOverlay
           onClose={closeScriptSelector}
-          onRunCommand={triggerCommand}
+          onSelectCommand={onSelectCommand}
+        />
+      )}
+      {parameterCommand && (
+        <ParameterForm
+          command={parameterCommand.command}
+          onRun={(parameters) =>
+            void triggerCommand(
+              parameterCommand.command.id,
+              parameters,
+              parameterCommand.preview,
+            )
+          }
+          onClose={() => setParameterCommand(undefined)}
+        />
+      )}
+      {userInputRequest && (
+        <ScriptInputDialog
+          key={userInputRequest.id}
+          request={userInputRequest}
+          onAnswer={answerUserInputRequest}
+          onCancel={cancelCommand}
+        />
+      )}
+      {preview && (
+        <DiffPreview
+          preview={preview}
+          onAccept={acceptPreview}
+          onReject={rejectPreview}
         />
       )}
     </div>
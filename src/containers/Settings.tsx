@@ -5,6 +5,8 @@ import { invoke } from '@tauri-apps/api/core'
 import { useSortedLanguages } from '../modules/languageKey.ts'
 import Checkbox from '../components/Checkbox.tsx'
 
+const scriptTimeoutOptions = [5, 10, 30, 60, 300]
+
 function Settings() {
   const settings = useSettings()
   const theme = useTheme()
@@ -47,6 +49,66 @@ function Settings() {
           void invoke('set_wrap_lines', { wrapLines: newValue })
         }}
       />
+      <Dropdown
+        title="Stop scripts after"
+        value={String(settings.script_timeout_seconds)}
+        onChange={(newValue) => {
+          void invoke('set_script_timeout', {
+            scriptTimeoutSeconds: Number(newValue),
+          })
+        }}
+      >
+        {scriptTimeoutOptions.map((seconds) => (
+          <option key={seconds} value={String(seconds)}>
+            {seconds < 60
+              ? `${seconds} seconds`
+              : `${seconds / 60} minute${seconds === 60 ? '' : 's'}`}
+          </option>
+        ))}
+      </Dropdown>
+      <div>
+        <span
+          className="block text-sm/6 font-medium"
+          style={{ color: theme.textColor }}
+        >
+          Script locations
+        </span>
+        <ul className="text-sm/6" style={{ color: theme.textColor }}>
+          {settings.script_locations.map((location) => (
+            <li key={location} className="flex items-center gap-2">
+              <span className="grow truncate" title={location}>
+                {location}
+              </span>
+              <button
+                type="button"
+                onClick={() => {
+                  void invoke('remove_script_location', { location })
+                }}
+              >
+                Remove
+              </button>
+            </li>
+          ))}
+        </ul>
+        <div className="flex gap-4 text-sm/6" style={{ color: theme.textColor }}>
+          <button
+            type="button"
+            onClick={() => {
+              void invoke('add_script_location')
+            }}
+          >
+            Add folder...
+          </button>
+          <button
+            type="button"
+            onClick={() => {
+              void invoke('open_user_script_directory')
+            }}
+          >
+            Open user scripts folder
+          </button>
+        </div>
+      </div>
     </div>
   )
 }
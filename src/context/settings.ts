@@ -5,6 +5,12 @@ export interface Settings {
   theme: 'System' | 'Light' | 'Dark'
   preferred_language: LanguageKey
   wrap_lines: boolean
+  script_timeout_seconds: number
+  script_locations: string[]
+  granted_permissions: Record<
+    string,
+    { read: string[]; write: string[]; files_hash: string }
+  >
 }
 
 export const SettingsContext = createContext<Settings | undefined>(undefined)
@@ -4,6 +4,7 @@ import CodeEditor from '../containers/CodeEditor.tsx'
 import SettingsProvider from '../components/SettingsProvider.tsx'
 import GlobalThemeDataSwitcher from '../components/GlobalThemeDataSwitcher.tsx'
 import { emit, emitTo } from '@tauri-apps/api/event'
+import { invoke } from '@tauri-apps/api/core'
 import { platform } from '@tauri-apps/plugin-os'
 import { getCurrentWindow } from '@tauri-apps/api/window'
 
@@ -22,6 +23,19 @@ if (platform() === 'windows') {
       case 'b':
         void emitTo(getCurrentWindow().label, 'open_picker', true)
         break
+      case 'B':
+        void invoke('reexecute_last_script')
+        break
+      case 'o':
+        void invoke('open_document')
+        break
+      case 's':
+      case 'S':
+        e.preventDefault()
+        void emitTo(getCurrentWindow().label, 'save_document', {
+          saveAs: e.shiftKey,
+        })
+        break
     }
   })
 }
@@ -0,0 +1,43 @@
+export type ParameterValue = string | number | boolean
+
+export type ParameterValues = Record<string, ParameterValue>
+
+interface BaseCommandParameter {
+  name: string
+  title: string
+  description?: string
+}
+
+export type CommandParameter = BaseCommandParameter &
+  (
+    | { type: 'string'; default?: string }
+    | { type: 'number'; default?: number; min?: number; max?: number }
+    | { type: 'boolean'; default?: boolean }
+    | { type: 'enum'; options: string[]; default?: string }
+  )
+
+export interface Command {
+  id: string
+  contentHash: string
+  title: string
+  description: string
+  parameters: CommandParameter[]
+}
+
+// The values the form starts out with. Parameters without a default start
+// empty, except for booleans and enums, which always have a value.
+export function initialParameterValues(
+  parameters: CommandParameter[],
+): ParameterValues {
+  const values: ParameterValues = {}
+  for (const parameter of parameters) {
+    if (parameter.default !== undefined && parameter.default !== null) {
+      values[parameter.name] = parameter.default
+    } else if (parameter.type === 'boolean') {
+      values[parameter.name] = false
+    } else if (parameter.type === 'enum') {
+      values[parameter.name] = parameter.options[0]
+    }
+  }
+  return values
+}
@@ -42,3 +42,7 @@ export function useSortedLanguages() {
 }
 
 export type LanguageKey = keyof typeof languages
+
+export function isLanguageKey(key: string): key is LanguageKey {
+  return Object.prototype.hasOwnProperty.call(languages, key)
+}
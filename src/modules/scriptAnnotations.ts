@@ -0,0 +1,209 @@
+import {
+  Decoration,
+  DecorationSet,
+  EditorState,
+  EditorView,
+  Extension,
+  GutterMarker,
+  Range,
+  RangeSet,
+  StateEffect,
+  StateField,
+  gutter,
+} from '@uiw/react-codemirror'
+
+export interface ScriptDiagnostic {
+  start: number
+  end: number
+  severity: 'info' | 'warning' | 'error'
+  message: string
+}
+
+export type ScriptDecoration =
+  | {
+      kind: 'highlight'
+      start: number
+      end: number
+      title?: string | null
+    }
+  | {
+      kind: 'gutterMarker'
+      position: number
+      text: string
+      title?: string | null
+    }
+
+// Diagnostics and decorations a script published for the document
+export interface DocumentAnnotations {
+  diagnostics: ScriptDiagnostic[]
+  decorations: ScriptDecoration[]
+}
+
+export const setAnnotations = StateEffect.define<DocumentAnnotations>()
+
+class TextGutterMarker extends GutterMarker {
+  constructor(
+    readonly text: string,
+    readonly title: string | undefined,
+    readonly className: string,
+  ) {
+    super()
+  }
+
+  eq(other: TextGutterMarker) {
+    return (
+      other.text === this.text &&
+      other.title === this.title &&
+      other.className === this.className
+    )
+  }
+
+  toDOM() {
+    const element = document.createElement('span')
+    element.className = this.className
+    element.textContent = this.text
+    if (this.title) {
+      element.title = this.title
+    }
+    return element
+  }
+}
+
+const severities: ScriptDiagnostic['severity'][] = ['info', 'warning', 'error']
+
+const severitySymbols: Record<ScriptDiagnostic['severity'], string> = {
+  info: 'ℹ',
+  warning: '⚠',
+  error: '✖',
+}
+
+interface AnnotationState {
+  marks: DecorationSet
+  gutterMarkers: RangeSet<GutterMarker>
+}
+
+const noAnnotations: AnnotationState = {
+  marks: Decoration.none,
+  gutterMarkers: RangeSet.empty,
+}
+
+function buildAnnotations(
+  annotations: DocumentAnnotations,
+  state: EditorState,
+): AnnotationState {
+  // Positions from scripts may point past the end of the document
+  const clamp = (position: number) => Math.min(position, state.doc.length)
+  const marks: Range<Decoration>[] = []
+  const gutterMarkers: Range<GutterMarker>[] = []
+  // Only the most severe diagnostic of a line is marked in the gutter
+  const lineDiagnostics = new Map<number, ScriptDiagnostic>()
+
+  for (const diagnostic of annotations.diagnostics) {
+    const from = clamp(diagnostic.start)
+    // An empty range would not show, so it underlines the next character
+    const to = Math.max(clamp(diagnostic.end), clamp(from + 1))
+    if (from < to) {
+      marks.push(
+        Decoration.mark({
+          class: `cm-scriptDiagnostic cm-scriptDiagnostic-${diagnostic.severity}`,
+          attributes: { title: diagnostic.message },
+        }).range(from, to),
+      )
+    }
+    const line = state.doc.lineAt(from).from
+    const current = lineDiagnostics.get(line)
+    if (
+      !current ||
+      severities.indexOf(diagnostic.severity) >
+        severities.indexOf(current.severity)
+    ) {
+      lineDiagnostics.set(line, diagnostic)
+    }
+  }
+  for (const [line, diagnostic] of lineDiagnostics) {
+    gutterMarkers.push(
+      new TextGutterMarker(
+        severitySymbols[diagnostic.severity],
+        diagnostic.message,
+        `cm-scriptGutterMarker-${diagnostic.severity}`,
+      ).range(line),
+    )
+  }
+
+  for (const decoration of annotations.decorations) {
+    if (decoration.kind === 'highlight') {
+      const from = clamp(decoration.start)
+      const to = clamp(decoration.end)
+      if (from < to) {
+        marks.push(
+          Decoration.mark({
+            class: 'cm-scriptHighlight',
+            attributes: decoration.title
+              ? { title: decoration.title }
+              : undefined,
+          }).range(from, to),
+        )
+      }
+    } else {
+      gutterMarkers.push(
+        new TextGutterMarker(
+          decoration.text,
+          decoration.title ?? undefined,
+          'cm-scriptGutterMarker',
+        ).range(state.doc.lineAt(clamp(decoration.position)).from),
+      )
+    }
+  }
+
+  return {
+    marks: Decoration.set(marks, true),
+    gutterMarkers: RangeSet.of(gutterMarkers, true),
+  }
+}
+
+const annotationField = StateField.define<AnnotationState>({
+  create: () => noAnnotations,
+  update(annotations, transaction) {
+    // The positions refer to the document the script saw, so any edit that
+    // happens after them removes the annotations
+    let updated = transaction.docChanged ? noAnnotations : annotations
+    for (const effect of transaction.effects) {
+      if (effect.is(setAnnotations)) {
+        updated = buildAnnotations(effect.value, transaction.state)
+      }
+    }
+    return updated
+  },
+  provide: (field) =>
+    EditorView.decorations.from(field, (annotations) => annotations.marks),
+})
+
+const annotationTheme = EditorView.baseTheme({
+  '.cm-scriptDiagnostic': {
+    textDecoration: 'underline wavy',
+    textDecorationSkipInk: 'none',
+    textUnderlineOffset: '3px',
+  },
+  '.cm-scriptDiagnostic-info': { textDecorationColor: '#3b82f6' },
+  '.cm-scriptDiagnostic-warning': { textDecorationColor: '#eab308' },
+  '.cm-scriptDiagnostic-error': { textDecorationColor: '#dc2626' },
+  '.cm-scriptHighlight': { backgroundColor: '#facc1566' },
+  '.cm-scriptAnnotationGutter .cm-gutterElement': {
+    padding: '0 3px',
+    cursor: 'default',
+  },
+  '.cm-scriptGutterMarker-info': { color: '#3b82f6' },
+  '.cm-scriptGutterMarker-warning': { color: '#eab308' },
+  '.cm-scriptGutterMarker-error': { color: '#dc2626' },
+})
+
+// Shows the diagnostics and decorations of scripts until the document is
+// edited
+export const scriptAnnotations: Extension = [
+  annotationField,
+  gutter({
+    class: 'cm-scriptAnnotationGutter',
+    markers: (view) => view.state.field(annotationField).gutterMarkers,
+  }),
+  annotationTheme,
+]
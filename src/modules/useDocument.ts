@@ -0,0 +1,119 @@
+import {
+  EditorView,
+  ReactCodeMirrorRef,
+  Transaction,
+  ViewUpdate,
+} from '@uiw/react-codemirror'
+import { invoke } from '@tauri-apps/api/core'
+import { UnlistenFn } from '@tauri-apps/api/event'
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow'
+import { useCallback, useEffect, useRef } from 'react'
+
+interface DocumentContents {
+  text: string
+  path?: string
+}
+
+interface SaveDocumentEvent {
+  saveAs: boolean
+}
+
+export default function useDocument(editorRef: ReactCodeMirrorRef | null) {
+  const dirtyRef = useRef(false)
+  const loadingRef = useRef(false)
+  // Counts the edits, so a save only marks the document as clean when no edit happened meanwhile
+  const revisionRef = useRef(0)
+  const pendingSavesRef = useRef(0)
+
+  const markEdited = useCallback(() => {
+    revisionRef.current += 1
+    // While a save is in progress every edit is reported, so the save does not clear it
+    if (dirtyRef.current && pendingSavesRef.current === 0) {
+      return
+    }
+    dirtyRef.current = true
+    invoke('set_document_dirty', {
+      dirty: true,
+      revision: revisionRef.current,
+    }).catch((e) => {
+      console.warn('Could not update document state', e)
+    })
+  }, [])
+
+  const loadDocument = useCallback((view: EditorView, text: string) => {
+    loadingRef.current = true
+    view.dispatch({
+      changes: [{ from: 0, to: view.state.doc.length, insert: text }],
+      selection: { anchor: 0 },
+      annotations: Transaction.addToHistory.of(false),
+    })
+    loadingRef.current = false
+    dirtyRef.current = false
+  }, [])
+
+  useEffect(() => {
+    const view = editorRef?.view
+    if (!view) {
+      return
+    }
+
+    invoke<DocumentContents | null>('take_pending_document')
+      .then((document) => {
+        if (document) {
+          loadDocument(view, document.text)
+        }
+      })
+      .catch((e) => {
+        console.warn('Could not load document', e)
+      })
+
+    const unlistenFns: Promise<UnlistenFn>[] = []
+    const window = getCurrentWebviewWindow()
+    unlistenFns.push(
+      window.listen<DocumentContents>('document_opened', (event) => {
+        loadDocument(view, event.payload.text)
+      }),
+    )
+    unlistenFns.push(
+      window.listen<SaveDocumentEvent>('save_document', (event) => {
+        const revision = revisionRef.current
+        pendingSavesRef.current += 1
+        invoke<boolean>('save_document', {
+          text: view.state.doc.toString(),
+          saveAs: event.payload.saveAs,
+          revision,
+        })
+          .then((saved) => {
+            if (saved && revisionRef.current === revision) {
+              dirtyRef.current = false
+            }
+          })
+          .catch((e) => {
+            console.warn('Could not save document', e)
+          })
+          .finally(() => {
+            pendingSavesRef.current -= 1
+          })
+      }),
+    )
+
+    return () => {
+      unlistenFns.forEach((unlistenFn) => {
+        void unlistenFn.then((unlisten) => unlisten())
+      })
+    }
+  }, [editorRef, loadDocument])
+
+  const onDocumentUpdate = useCallback(
+    (update: ViewUpdate) => {
+      if (update.docChanged && !loadingRef.current) {
+        markEdited()
+      }
+    },
+    [markEdited],
+  )
+
+  return {
+    onDocumentUpdate,
+  }
+}
@@ -1,11 +1,19 @@
 import {
+  ChangeSet,
   EditorSelection,
+  EditorState,
   EditorView,
   ReactCodeMirrorRef,
   SelectionRange,
+  TransactionSpec,
 } from '@uiw/react-codemirror'
 import { Channel, invoke } from '@tauri-apps/api/core'
-import { useCallback, useState } from 'react'
+import { UnlistenFn } from '@tauri-apps/api/event'
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow'
+import { useCallback, useEffect, useState } from 'react'
+import { ParameterValues } from './commandParameters.ts'
+import { LanguageKey, isLanguageKey } from './languageKey.ts'
+import { DocumentAnnotations, setAnnotations } from './scriptAnnotations.ts'
 
 interface GetPartialTextRequestEvent {
   id: number
@@ -21,17 +29,176 @@ interface ReplaceSelectionsRequestEvent {
   data: { index: number; text: string }[]
 }
 
+type EditRequestEvent =
+  | {
+      event: 'setFullText' | 'appendText'
+      data: string
+    }
+  | ReplaceSelectionsRequestEvent
+  | {
+      event: 'setSelections'
+      data: {
+        mainSelectionIndex: number
+        selections: { anchor: number; head: number }[]
+      }
+    }
+  | {
+      event: 'insertAt'
+      data: { position: number; text: string }
+    }
+  | {
+      event: 'replaceRange'
+      data: { start: number; end: number; text: string }
+    }
+
+// Changes a script makes outside of the document itself
+interface SetLanguageRequestEvent {
+  event: 'setLanguage'
+  data: string
+}
+interface SetAnnotationsRequestEvent {
+  event: 'setAnnotations'
+  data: DocumentAnnotations
+}
+
+type ScriptEdit =
+  | EditRequestEvent
+  | SetLanguageRequestEvent
+  | SetAnnotationsRequestEvent
+
+interface PromptRequestEvent {
+  id: number
+  event: 'prompt'
+  data: { message: string; defaultValue?: string }
+}
+interface ConfirmRequestEvent {
+  id: number
+  event: 'confirm'
+  data: { message: string }
+}
+interface PickFromListRequestEvent {
+  id: number
+  event: 'pickFromList'
+  data: { message: string; options: string[] }
+}
+
+// Questions a running script asks the user
+export type UserInputRequest =
+  | PromptRequestEvent
+  | ConfirmRequestEvent
+  | PickFromListRequestEvent
+
 type EditorRequestEvent =
   | {
       id: number
       event: 'getFullText'
     }
   | {
-      event: 'setFullText' | 'setError'
+      id: number
+      event: 'getDocumentLength' | 'getCursor'
+    }
+  | {
+      event: 'setError'
       data: string
     }
+  | {
+      event: 'setStatus'
+      data: ScriptStatus
+    }
   | GetPartialTextRequestEvent
-  | ReplaceSelectionsRequestEvent
+  | ScriptEdit
+  | {
+      event: 'applyEdits'
+      data: ScriptEdit[]
+    }
+  | {
+      event: 'log'
+      data: unknown
+    }
+  | UserInputRequest
+
+// A message a script shows in the status bar. It is dismissed after duration
+// milliseconds, or when the next command starts.
+export interface ScriptStatus {
+  level: 'info' | 'success' | 'warning' | 'error'
+  message: string
+  duration?: number | null
+}
+
+export interface DiffLine {
+  kind: 'unchanged' | 'deleted' | 'inserted'
+  text: string
+  // Start and end offsets of the changed parts of the line
+  highlights: [number, number][]
+}
+
+export interface DiffHunk {
+  oldStart: number
+  oldCount: number
+  newStart: number
+  newCount: number
+  lines: DiffLine[]
+}
+
+// The changes a script run in preview mode would make, which are only applied
+// once the user accepts them
+export interface ScriptPreview {
+  diff: {
+    hunks: DiffHunk[]
+    deletedLines: number
+    insertedLines: number
+  }
+  edits: ScriptEdit[]
+}
+
+type ScriptRunOutcome = 'completed' | 'cancelled' | { preview: ScriptPreview }
+
+export interface ScriptStackFrame {
+  functionName?: string
+  file?: string
+  line?: number
+  column?: number
+}
+
+export interface ScriptError {
+  message: string
+  file?: string
+  line?: number
+  column?: number
+  stack: ScriptStackFrame[]
+}
+
+function toScriptError(error: unknown): ScriptError {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return error as ScriptError
+  }
+  return { message: String(error), stack: [] }
+}
+
+interface RunScriptCommandEvent {
+  commandId: string
+  parameters: ParameterValues
+}
+
+// Moves a position that falls between the two halves of a surrogate pair back
+// to the start of the character, so that a slice never holds half a character
+function toCharacterBoundary(view: EditorView, position: number): number {
+  if (position <= 0 || position >= view.state.doc.length) {
+    return position
+  }
+  const pair = view.state.sliceDoc(position - 1, position + 1)
+  const first = pair.charCodeAt(0)
+  const second = pair.charCodeAt(1)
+  if (
+    first >= 0xd800 &&
+    first <= 0xdbff &&
+    second >= 0xdc00 &&
+    second <= 0xdfff
+  ) {
+    return position - 1
+  }
+  return position
+}
 
 function onGetPartialText(event: GetPartialTextRequestEvent, view: EditorView) {
   let text: string | undefined = undefined
@@ -53,7 +220,10 @@ function onGetPartialText(event: GetPartialTextRequestEvent, view: EditorView) {
     event.data.start !== null &&
     event.data.end !== null
   ) {
-    text = view.state.sliceDoc(event.data.start, event.data.end)
+    text = view.state.sliceDoc(
+      toCharacterBoundary(view, event.data.start),
+      toCharacterBoundary(view, event.data.end),
+    )
   }
   if (text !== undefined) {
     void invoke('reply_editor_request', {
@@ -74,15 +244,15 @@ function onGetPartialText(event: GetPartialTextRequestEvent, view: EditorView) {
   }
 }
 
-function onReplaceSelections(
+function replaceSelections(
   event: ReplaceSelectionsRequestEvent,
-  view: EditorView,
-) {
+  state: EditorState,
+): TransactionSpec {
   const transforms: (string | null)[] = new Array<string | null>(
-    view.state.selection.ranges.length,
+    state.selection.ranges.length,
   ).fill(null)
   const changes = event.data.flatMap((replacement) => {
-    const selection = view.state.selection.ranges[replacement.index]
+    const selection = state.selection.ranges[replacement.index]
     if (!selection) {
       return []
     }
@@ -101,7 +271,7 @@ function onReplaceSelections(
   })
   let currentShift = 0
   const ranges = transforms.map((text, index) => {
-    const previousRange = view.state.selection.ranges[index]
+    const previousRange = state.selection.ranges[index]
     if (text === null && currentShift === 0) {
       return previousRange
     }
@@ -128,22 +298,120 @@ function onReplaceSelections(
     })
   })
 
+  return {
+    changes,
+    selection: EditorSelection.create(ranges, state.selection.mainIndex),
+  }
+}
+
+function editTransaction(
+  edit: EditRequestEvent,
+  state: EditorState,
+): TransactionSpec {
+  // Positions from scripts may point past the end of the document
+  const clamp = (position: number) => Math.min(position, state.doc.length)
+  switch (edit.event) {
+    case 'setFullText':
+      return {
+        changes: { from: 0, to: state.doc.length, insert: edit.data },
+      }
+    case 'appendText':
+      return { changes: { from: state.doc.length, insert: edit.data } }
+    case 'replaceSelections':
+      return replaceSelections(edit, state)
+    case 'setSelections':
+      return {
+        selection: EditorSelection.create(
+          edit.data.selections.map(({ anchor, head }) =>
+            EditorSelection.range(clamp(anchor), clamp(head)),
+          ),
+          edit.data.mainSelectionIndex,
+        ),
+      }
+    case 'insertAt':
+      return {
+        changes: { from: clamp(edit.data.position), insert: edit.data.text },
+      }
+    case 'replaceRange':
+      return {
+        changes: {
+          from: clamp(edit.data.start),
+          to: clamp(edit.data.end),
+          insert: edit.data.text,
+        },
+      }
+  }
+}
+
+// Applies the edits of a script as a single transaction, so that they can be
+// undone in one step
+function onApplyEdits(
+  edits: ScriptEdit[],
+  view: EditorView,
+  setLanguage: (language: string) => void,
+) {
+  let state = view.state
+  let changes = ChangeSet.empty(state.doc.length)
+  // Like in the editor, later edits of the document remove the annotations
+  let annotations: DocumentAnnotations | undefined = undefined
+  for (const edit of edits) {
+    if (edit.event === 'setLanguage') {
+      setLanguage(edit.data)
+      continue
+    }
+    if (edit.event === 'setAnnotations') {
+      annotations = edit.data
+      continue
+    }
+    const transaction = state.update(editTransaction(edit, state))
+    changes = changes.compose(transaction.changes)
+    state = transaction.state
+    if (transaction.docChanged) {
+      annotations = undefined
+    }
+  }
   view.dispatch({
     changes,
-    selection: EditorSelection.create(ranges, view.state.selection.mainIndex),
+    selection: state.selection,
+    effects: annotations ? setAnnotations.of(annotations) : [],
   })
 }
 
 export default function useScriptCommandRunner(
   editorRef: ReactCodeMirrorRef | null,
+  language: LanguageKey,
+  onLanguageChange: (language: LanguageKey) => void,
 ) {
   const [scriptState, setScriptState] = useState<{
     running: boolean
-    error?: string
+    cancelled?: boolean
+    error?: ScriptError
   }>({ running: false })
+  const [logVersion, setLogVersion] = useState(0)
+  const [userInputRequest, setUserInputRequest] = useState<UserInputRequest>()
+  const [status, setStatus] = useState<ScriptStatus>()
+  const [preview, setPreview] = useState<
+    ScriptPreview & { doc: EditorState['doc'] }
+  >()
+
+  // Scripts may ask for any language, but only the known ones can be shown
+  const setLanguage = useCallback(
+    (language: string) => {
+      if (isLanguageKey(language)) {
+        onLanguageChange(language)
+      } else {
+        console.warn('Script set an unknown language', language)
+      }
+    },
+    [onLanguageChange],
+  )
 
   const triggerCommand = useCallback(
-    async (commandId: string) => {
+    async (
+      commandId: string,
+      parameters?: ParameterValues,
+      preview?: boolean,
+    ) => {
       const currentScriptState = { ...scriptState }
       if (currentScriptState.running || !editorRef || !editorRef?.view) {
         return
@@ -164,33 +432,78 @@ export default function useScriptCommandRunner(
           case 'getPartialText':
             onGetPartialText(response, editorRef.view!)
             break
+          case 'getDocumentLength':
+            void invoke('reply_editor_request', {
+              reply: {
+                id: response.id,
+                event: 'getDocumentLength',
+                data: editorRef.view?.state?.doc.length,
+              },
+            })
+            break
+          case 'getCursor':
+            void invoke('reply_editor_request', {
+              reply: {
+                id: response.id,
+                event: 'getCursor',
+                data: editorRef.view?.state?.selection.main.head,
+              },
+            })
+            break
           case 'setFullText':
+          case 'appendText':
+          case 'replaceSelections':
+          case 'setSelections':
+          case 'insertAt':
+          case 'replaceRange':
+            editorRef.view?.dispatch(
+              editTransaction(response, editorRef.view!.state),
+            )
+            break
+          case 'setLanguage':
+            setLanguage(response.data)
+            break
+          case 'setAnnotations':
             editorRef.view?.dispatch({
-              changes: [
-                {
-                  from: 0,
-                  to: editorRef.view?.state?.doc?.length,
-                  insert: response.data,
-                },
-              ],
+              effects: setAnnotations.of(response.data),
             })
             break
-          case 'replaceSelections':
-            onReplaceSelections(response, editorRef.view!)
+          case 'applyEdits':
+            onApplyEdits(response.data, editorRef.view!, setLanguage)
+            break
+          case 'log':
+            setLogVersion((version) => version + 1)
+            break
+          case 'prompt':
+          case 'confirm':
+          case 'pickFromList':
+            setUserInputRequest(response)
             break
           case 'setError':
-            currentScriptState.error = response.data
+            currentScriptState.error = { message: response.data, stack: [] }
             setScriptState({ ...currentScriptState })
             break
+          case 'setStatus':
+            setStatus(response.data)
+            break
         }
       }
 
       currentScriptState.running = true
       delete currentScriptState.error
+      delete currentScriptState.cancelled
       setScriptState({ ...currentScriptState })
+      setStatus(undefined)
+      // A new run shows up in the log as soon as it starts
+      setLogVersion((version) => version + 1)
       try {
-        const selection = editorRef.view.state.selection
+        const { selection, doc } = editorRef.view.state
         const editorState = {
+          document: {
+            language,
+            length: doc.length,
+            lineCount: doc.lines,
+          },
           selection: {
             mainSelectionIndex: selection.mainIndex,
             selections: selection.ranges.map((range) => {
@@ -210,28 +523,137 @@ export default function useScriptCommandRunner(
             }),
           },
         }
-        await invoke('run_script_command', {
+        const outcome = await invoke<ScriptRunOutcome>('run_script_command', {
           commandId,
+          parameters,
+          preview,
           editorRequestChannel,
           editorState,
         })
         currentScriptState.running = false
+        currentScriptState.cancelled = outcome === 'cancelled'
         setScriptState({ ...currentScriptState })
-        console.log('Script ran successfully')
+        setUserInputRequest(undefined)
+        if (typeof outcome === 'object') {
+          // Remembers the document the preview was made for
+          setPreview({ ...outcome.preview, doc: editorRef.view.state.doc })
+        }
+        console.log(
+          outcome === 'cancelled'
+            ? 'Script was cancelled'
+            : 'Script ran successfully',
+        )
       } catch (e) {
-        const error = e as string
-        console.warn(`Error running script: ${error}`)
+        const error = toScriptError(e)
+        console.warn('Error running script', error)
         currentScriptState.running = false
         currentScriptState.error = error
         setScriptState({ ...currentScriptState })
+        setUserInputRequest(undefined)
       }
     },
-    [scriptState, setScriptState, editorRef],
+    [scriptState, setScriptState, editorRef, language, setLanguage],
   )
 
+  useEffect(() => {
+    // Sent when the last script of this window should be executed again
+    let unlistenFn: UnlistenFn | undefined = undefined
+    void (async () => {
+      const window = getCurrentWebviewWindow()
+      unlistenFn = await window.listen<RunScriptCommandEvent>(
+        'run_script_command',
+        (event) => {
+          void triggerCommand(
+            event.payload.commandId,
+            event.payload.parameters,
+          )
+        },
+      )
+    })()
+
+    return () => unlistenFn?.()
+  }, [triggerCommand])
+
+  const cancelCommand = useCallback(() => {
+    invoke('cancel_script_command').catch((e) => {
+      console.warn('Error cancelling script', e)
+    })
+  }, [])
+
+  const answerUserInputRequest = useCallback(
+    (request: UserInputRequest, answer: string | boolean | number) => {
+      setUserInputRequest(undefined)
+      invoke('reply_editor_request', {
+        reply: {
+          id: request.id,
+          event: request.event,
+          data: answer,
+        },
+      }).catch((e) => {
+        console.warn('Error answering script', e)
+      })
+    },
+    [],
+  )
+
+  const acceptPreview = useCallback(() => {
+    const view = editorRef?.view
+    if (!preview || !view) {
+      return
+    }
+    setPreview(undefined)
+    if (view.state.doc !== preview.doc) {
+      setScriptState({
+        running: false,
+        error: {
+          message: 'The document changed since the preview was made',
+          stack: [],
+        },
+      })
+      return
+    }
+    onApplyEdits(preview.edits, view, setLanguage)
+    // Only an accepted preview becomes the script to re-execute
+    invoke('accept_script_preview').catch((e) => {
+      console.warn('Error accepting preview', e)
+    })
+  }, [editorRef, preview, setLanguage])
+
+  const rejectPreview = useCallback(() => setPreview(undefined), [])
+
+  useEffect(() => {
+    if (!status?.duration) {
+      return
+    }
+    const timeout = setTimeout(() => setStatus(undefined), status.duration)
+    return () => clearTimeout(timeout)
+  }, [status])
+
+  useEffect(() => {
+    if (!scriptState.running) {
+      return
+    }
+    const onKeyDown = (e: KeyboardEvent) => {
+      if (e.key === 'Escape') {
+        cancelCommand()
+      }
+    }
+    document.addEventListener('keydown', onKeyDown)
+    return () => document.removeEventListener('keydown', onKeyDown)
+  }, [scriptState.running, cancelCommand])
+
   return {
     isRunning: scriptState.running,
+    wasCancelled: scriptState.cancelled ?? false,
     error: scriptState.error,
+    status,
+    logVersion,
+    userInputRequest,
+    answerUserInputRequest,
+    preview,
+    acceptPreview,
+    rejectPreview,
     triggerCommand,
+    cancelCommand,
   }
 }